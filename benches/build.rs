@@ -0,0 +1,73 @@
+//! Build throughput across term types, with both interning hashers.
+//!
+//! ```text
+//! cargo bench --bench build
+//! ```
+
+use std::hash::Hash;
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use logify::Expression;
+
+const LEAVES: usize = 1 << 20;
+const GROUP: usize = 8;
+
+// interns every leaf, with an AND per group of leaves under one OR root
+fn build<T: Hash + PartialEq>(
+    mut expr: Expression<T>,
+    terms: impl Iterator<Item = T>,
+) -> Expression<T> {
+    let mut group = Vec::with_capacity(GROUP);
+    let mut ands = Vec::with_capacity(LEAVES / GROUP);
+    for term in terms {
+        group.push(expr.set(term));
+        if group.len() == GROUP {
+            ands.push(expr.intersection(group.drain(..)));
+        }
+    }
+    let root = expr.union(ands);
+    expr.add_root(root);
+    expr
+}
+
+fn empty<T>(fast: bool) -> Expression<T> {
+    if fast {
+        Expression::with_fast_hash()
+    } else {
+        Expression::new()
+    }
+}
+
+fn bench_build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(LEAVES as u64));
+
+    let strings: Vec<String> = (0..LEAVES / 2).map(|i| format!("term-{i}")).collect();
+    for fast in [false, true] {
+        let hash = if fast { "fast" } else { "quality" };
+        group.bench_function(BenchmarkId::new(format!("u32/{hash}"), LEAVES), |b| {
+            b.iter(|| {
+                let terms = (0..LEAVES as u32).map(|i| i % (LEAVES as u32 / 2));
+                build(empty(fast), terms)
+            })
+        });
+        group.bench_function(BenchmarkId::new(format!("&str/{hash}"), LEAVES), |b| {
+            b.iter(|| {
+                let terms = (0..LEAVES).map(|i| strings[i % strings.len()].as_str());
+                build(empty(fast), terms)
+            })
+        });
+        group.bench_function(BenchmarkId::new(format!("String/{hash}"), LEAVES), |b| {
+            b.iter(|| {
+                let terms = (0..LEAVES).map(|i| strings[i % strings.len()].clone());
+                build(empty(fast), terms)
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_build);
+criterion_main!(benches);
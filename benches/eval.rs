@@ -0,0 +1,107 @@
+//! Evaluation throughput on a wide, heavily edited expression, before and after
+//! `reorder_for_evaluation`. The expression is larger than the CPU cache, which is where the
+//! node layout matters.
+//!
+//! ```text
+//! cargo bench --bench eval
+//! ```
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use logify::{Evaluator, EvaluatorCache, Expression};
+
+const LEAVES: u32 = 1 << 20;
+const GROUPS: u32 = 1 << 18;
+const GROUP: u32 = 8;
+const ROOTS: u32 = 64;
+
+// leaves are created up front and picked by a scattering stride, every group is preceded by
+// a discarded draft, and the roots are absorbed from separately built expressions
+fn edited() -> Expression<u32> {
+    let mut expr = Expression::new();
+    for term in 0..LEAVES {
+        expr.set(term);
+    }
+    let leaf = |expr: &mut Expression<u32>, i: u32| expr.set(i.wrapping_mul(40_503) % LEAVES);
+
+    let mut ands = Vec::with_capacity(GROUPS as usize);
+    for g in 0..GROUPS {
+        let draft: Vec<_> = (0..GROUP)
+            .map(|k| leaf(&mut expr, g * GROUP + k + 1))
+            .collect();
+        expr.union(draft);
+        let kids: Vec<_> = (0..GROUP).map(|k| leaf(&mut expr, g * GROUP + k)).collect();
+        ands.push(expr.intersection(kids));
+    }
+    for chunk in ands.chunks((GROUPS / ROOTS) as usize) {
+        let root = expr.union(chunk.iter().copied());
+        expr.add_root(root);
+    }
+
+    let extra = (0..ROOTS).map(|r| {
+        let mut part = Expression::new();
+        let kids: Vec<_> = (0..GROUP).map(|k| part.set(r * GROUP + k)).collect();
+        let root = part.intersection(kids);
+        part.add_root(root);
+        part
+    });
+    expr.extend(extra);
+    expr
+}
+
+// a term is true when divisible by three, so the solver costs almost nothing next to the
+// walk over the nodes
+struct Thirds;
+
+impl Evaluator<u32, bool, ()> for Thirds {
+    fn get_universal(&mut self) -> Result<bool, ()> {
+        Ok(true)
+    }
+    fn get_empty(&mut self) -> Result<bool, ()> {
+        Ok(false)
+    }
+    fn eval_set(&mut self, set: &u32) -> Result<bool, ()> {
+        Ok(set.is_multiple_of(3))
+    }
+    fn eval_union<'a, I>(&mut self, values: I) -> Result<bool, ()>
+    where
+        I: IntoIterator<Item = &'a bool>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        Ok(values.into_iter().any(|&v| v))
+    }
+    fn eval_intersection<'a, I>(&mut self, values: I) -> Result<bool, ()>
+    where
+        I: IntoIterator<Item = &'a bool>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        Ok(values.into_iter().all(|&v| v))
+    }
+    fn eval_difference(&mut self, include: &bool, exclude: &bool) -> Result<bool, ()> {
+        Ok(*include && !*exclude)
+    }
+}
+
+fn bench_eval(c: &mut Criterion) {
+    let mut group = c.benchmark_group("evaluate_with");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements((GROUPS * GROUP) as u64));
+
+    let mut solver = Thirds;
+
+    let expr = edited();
+    let reordered = expr.clone().reorder_for_evaluation();
+    for (name, expr) in [("edited", &expr), ("reordered", &reordered)] {
+        let mut cache = EvaluatorCache::new();
+        group.bench_function(BenchmarkId::new(name, expr.node_count()), |b| {
+            b.iter(|| {
+                cache.clear();
+                expr.evaluate_with(&mut solver, &mut cache)
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_eval);
+criterion_main!(benches);
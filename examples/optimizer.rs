@@ -7,6 +7,7 @@ use logify::{
 
 // Geographical locations
 #[derive(PartialEq, Hash, Clone, Debug)]
+#[allow(clippy::upper_case_acronyms)]
 enum Geo {
     USA,
     California,
@@ -55,6 +56,7 @@ fn main() {
         merger: GeoMerger,
         merger_depth: 2,
         max_iterations: 0,
+        factor_intersections: false,
     };
 
     // Example 1. California is inside of USA, so it will be redacted
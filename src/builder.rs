@@ -1,9 +1,16 @@
-use std::{cell::RefCell, hash::Hash};
+use std::{
+    cell::{Cell, RefCell},
+    fmt::{self, Display},
+    hash::Hash,
+};
 
-use slotmap::{SlotMap, new_key_type};
+use slotmap::{SecondaryMap, SlotMap, new_key_type};
 
 mod convert;
 mod logic_node;
+mod mut_builder;
+
+pub use mut_builder::MutBuilder;
 
 new_key_type! {
     /// A lightweight handle to a node within an [`ExpressionBuilder`].
@@ -48,6 +55,32 @@ pub enum BuilderNode<T> {
     Not(NodeHandle),
 }
 
+/// An error returned when a [`NodeHandle`] does not refer to the expected kind of node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeError {
+    /// The handle does not exist in this builder.
+    InvalidNode,
+    /// The node is not a Union or Intersection.
+    ExpectedGroup,
+    /// The node is not a Set.
+    ExpectedLeaf,
+    /// The child is the parent itself or one of its ancestors, so adding it would form a cycle.
+    Cycle,
+}
+
+impl Display for NodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeError::InvalidNode => write!(f, "handle does not exist in this builder"),
+            NodeError::ExpectedGroup => write!(f, "expected a Union or Intersection node"),
+            NodeError::ExpectedLeaf => write!(f, "expected a Set node"),
+            NodeError::Cycle => write!(f, "the child already contains the parent"),
+        }
+    }
+}
+
+impl std::error::Error for NodeError {}
+
 /// A staging area for constructing logical expressions.
 ///
 /// The `ExpressionBuilder` allows you to create complex logical relationships incrementally.
@@ -118,6 +151,7 @@ pub enum BuilderNode<T> {
 pub struct ExpressionBuilder<T> {
     pub nodes: RefCell<SlotMap<NodeHandle, BuilderNode<T>>>,
     pub roots: RefCell<Vec<NodeHandle>>,
+    strict: Cell<bool>,
 }
 
 impl<T> Default for ExpressionBuilder<T> {
@@ -125,6 +159,7 @@ impl<T> Default for ExpressionBuilder<T> {
         Self {
             nodes: RefCell::new(SlotMap::with_key()),
             roots: RefCell::new(Vec::new()),
+            strict: Cell::new(false),
         }
     }
 }
@@ -165,8 +200,12 @@ impl<T> ExpressionBuilder<T> {
     ///
     /// # Arguments
     /// * `kids` - An iterator of `NodeHandle`s (or items that convert into them).
+    ///
+    /// # Panics
+    /// In [strict mode](Self::strict_groups), panics if `kids` is empty.
     pub fn union<H: Into<NodeHandle>>(&self, kids: impl IntoIterator<Item = H>) -> NodeHandle {
-        let kids = kids.into_iter().map(|h| h.into()).collect();
+        let kids: Vec<_> = kids.into_iter().map(|h| h.into()).collect();
+        self.check_group(&kids, "union");
         self.nodes.borrow_mut().insert(BuilderNode::Union(kids))
     }
 
@@ -176,16 +215,49 @@ impl<T> ExpressionBuilder<T> {
     ///
     /// # Arguments
     /// * `kids` - An iterator of `NodeHandle`s (or items that convert into them).
+    ///
+    /// # Panics
+    /// In [strict mode](Self::strict_groups), panics if `kids` is empty.
     pub fn intersection<H: Into<NodeHandle>>(
         &self,
         kids: impl IntoIterator<Item = H>,
     ) -> NodeHandle {
-        let kids = kids.into_iter().map(|h| h.into()).collect();
+        let kids: Vec<_> = kids.into_iter().map(|h| h.into()).collect();
+        self.check_group(&kids, "intersection");
         self.nodes
             .borrow_mut()
             .insert(BuilderNode::Intersection(kids))
     }
 
+    /// Rejects empty groups from now on.
+    ///
+    /// By default, an empty Union becomes Empty and an empty Intersection becomes Universal
+    /// when built. In generated code that is usually a bug (a filter with no selected tags
+    /// silently matching nothing, or everything), so in strict mode [`union`](Self::union) and
+    /// [`intersection`](Self::intersection) panic as soon as they are given no children. Use
+    /// [`empty`](Self::empty) and [`universal`](Self::universal) for intended constants.
+    ///
+    /// # Example
+    /// ```rust,should_panic
+    /// use logify::ExpressionBuilder;
+    ///
+    /// let builder = ExpressionBuilder::<&str>::new();
+    /// builder.strict_groups();
+    ///
+    /// let selected_tags: Vec<&str> = Vec::new();
+    /// // panics: "empty union in strict mode"
+    /// let filter = builder.union(selected_tags.into_iter().map(|t| builder.set(t)));
+    /// ```
+    pub fn strict_groups(&self) {
+        self.strict.set(true);
+    }
+
+    fn check_group(&self, kids: &[NodeHandle], kind: &str) {
+        if self.strict.get() && kids.is_empty() {
+            panic!("empty {} in strict mode", kind);
+        }
+    }
+
     /// Creates a Complement (NOT) node.
     ///
     /// Represents the inverse of the child node.
@@ -195,6 +267,68 @@ impl<T> ExpressionBuilder<T> {
             .insert(BuilderNode::Not(child.into()))
     }
 
+    /// Creates a material implication (`IF A THEN B`), stored as `!A | B`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{ExpressionBuilder, eval::BoolEval};
+    ///
+    /// let builder = ExpressionBuilder::<&str>::new();
+    /// let admin = builder.set("Admin");
+    /// let audited = builder.set("Audited");
+    /// builder.add_root(builder.implies(admin, audited));
+    /// let expr = builder.build();
+    ///
+    /// // only an unaudited admin breaks the rule
+    /// let mut solver = BoolEval::new();
+    /// solver.add("Admin");
+    /// assert_eq!(expr.evaluate(&mut solver), Ok(vec![false]));
+    /// assert_eq!(expr.evaluate(&mut BoolEval::new()), Ok(vec![true]));
+    /// ```
+    pub fn implies<A: Into<NodeHandle>, C: Into<NodeHandle>>(
+        &self,
+        antecedent: A,
+        consequent: C,
+    ) -> NodeHandle {
+        let not_antecedent = self.not(antecedent);
+        self.union([not_antecedent, consequent.into()])
+    }
+
+    /// Creates a multiplexer (`IF C THEN T ELSE E`), stored as `(C & T) | (!C & E)`.
+    ///
+    /// If both branches are the same handle, it is returned directly. Constant conditions
+    /// are folded when the builder is compiled.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::ExpressionBuilder;
+    ///
+    /// let builder = ExpressionBuilder::<&str>::new();
+    /// let route = builder.if_then_else(builder.set("Internal"), builder.set("LanOk"), builder.set("VpnOk"));
+    /// let always = builder.if_then_else(builder.universal(), builder.set("LanOk"), builder.set("VpnOk"));
+    /// builder.add_root(route);
+    /// builder.add_root(always);
+    /// let expr = builder.build();
+    ///
+    /// let roots: Vec<_> = expr.roots().map(|r| expr.to_string(r)).collect();
+    /// assert_eq!(roots, ["(([Internal] & [LanOk]) | ([Internal]' & [VpnOk]))", "[LanOk]"]);
+    /// ```
+    pub fn if_then_else<C, A, B>(&self, cond: C, then: A, els: B) -> NodeHandle
+    where
+        C: Into<NodeHandle>,
+        A: Into<NodeHandle>,
+        B: Into<NodeHandle>,
+    {
+        let (cond, then, els) = (cond.into(), then.into(), els.into());
+        if then == els {
+            return then;
+        }
+        let on_true = self.intersection([cond, then]);
+        let not_cond = self.not(cond);
+        let on_false = self.intersection([not_cond, els]);
+        self.union([on_true, on_false])
+    }
+
     /// Marks a node as a "Root".
     ///
     /// Roots are the entry points of the expression. When [`ExpressionBuilder::build`]
@@ -203,6 +337,193 @@ impl<T> ExpressionBuilder<T> {
         self.roots.borrow_mut().push(root.into());
     }
 
+    /// Removes the root at `index`, returning its handle.
+    ///
+    /// The node itself is kept, so the handle stays usable; it just won't survive
+    /// [`build`](Self::build) unless it is reachable from another root.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of range.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::ExpressionBuilder;
+    ///
+    /// let builder = ExpressionBuilder::<&str>::new();
+    /// let a = builder.leaf("A");
+    /// builder.add_root(a);
+    /// builder.add_root(builder.leaf("B"));
+    ///
+    /// assert_eq!(builder.remove_root(0), a.handle());
+    /// let expr = builder.build();
+    /// assert_eq!(expr.root_count(), 1);
+    /// assert_eq!(expr.to_string(&expr.roots_owned()[0]), "[B]");
+    /// ```
+    pub fn remove_root(&self, index: usize) -> NodeHandle {
+        self.roots.borrow_mut().remove(index)
+    }
+
+    /// Deletes a node, returning it, or `None` if the handle was already gone.
+    ///
+    /// Every `Union`, `Intersection`, or `Not` that referenced the node is rewired to a fresh
+    /// Empty node instead, so a retracted branch of an OR simply disappears. Roots are left
+    /// alone. Any remaining copy of the handle, whether a root or one held by the caller, is
+    /// dangling and resolves to Empty when built.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::ExpressionBuilder;
+    ///
+    /// let builder = ExpressionBuilder::<&str>::new();
+    /// let a = builder.set("A");
+    /// let speculative = builder.set("B");
+    /// let root = builder.union([a, speculative]);
+    /// builder.add_root(root);
+    ///
+    /// assert!(builder.remove(speculative).is_some());
+    /// assert!(builder.remove(speculative).is_none());
+    ///
+    /// let expr = builder.build();
+    /// assert_eq!(expr.to_string(&expr.roots_owned()[0]), "[A]");
+    /// ```
+    pub fn remove(&self, handle: NodeHandle) -> Option<BuilderNode<T>> {
+        let mut nodes = self.nodes.borrow_mut();
+        let removed = nodes.remove(handle)?;
+
+        let refers = |node: &BuilderNode<T>| match node {
+            BuilderNode::Union(kids) | BuilderNode::Intersection(kids) => kids.contains(&handle),
+            BuilderNode::Not(kid) => *kid == handle,
+            _ => false,
+        };
+        if nodes.values().any(refers) {
+            let empty = nodes.insert(BuilderNode::Empty);
+            for node in nodes.values_mut() {
+                match node {
+                    BuilderNode::Union(kids) | BuilderNode::Intersection(kids) => {
+                        for kid in kids.iter_mut().filter(|kid| **kid == handle) {
+                            *kid = empty;
+                        }
+                    }
+                    BuilderNode::Not(kid) if *kid == handle => *kid = empty,
+                    _ => {}
+                }
+            }
+        }
+        Some(removed)
+    }
+
+    /// Checks that a handle refers to a Union or Intersection node.
+    ///
+    /// Useful as a guard in generator code, failing at the point a handle is misused rather
+    /// than producing a subtly wrong expression.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{ExpressionBuilder, builder::NodeError};
+    ///
+    /// let builder = ExpressionBuilder::<&str>::new();
+    /// let a = builder.set("A");
+    /// let group = builder.union([a]);
+    ///
+    /// assert_eq!(builder.expect_group(group), Ok(()));
+    /// assert_eq!(builder.expect_group(a), Err(NodeError::ExpectedGroup));
+    /// ```
+    pub fn expect_group(&self, handle: NodeHandle) -> Result<(), NodeError> {
+        match self.nodes.borrow().get(handle) {
+            Some(BuilderNode::Union(_) | BuilderNode::Intersection(_)) => Ok(()),
+            Some(_) => Err(NodeError::ExpectedGroup),
+            None => Err(NodeError::InvalidNode),
+        }
+    }
+
+    /// Checks that a handle refers to a Set (leaf) node.
+    ///
+    /// See [`expect_group`](Self::expect_group).
+    pub fn expect_leaf(&self, handle: NodeHandle) -> Result<(), NodeError> {
+        match self.nodes.borrow().get(handle) {
+            Some(BuilderNode::Set(_)) => Ok(()),
+            Some(_) => Err(NodeError::ExpectedLeaf),
+            None => Err(NodeError::InvalidNode),
+        }
+    }
+
+    /// Appends `child` to an existing Union or Intersection.
+    ///
+    /// Useful when more conditions are discovered after a group was created. Every other
+    /// handle to the group sees the new child, since the node is edited in place.
+    ///
+    /// # Errors
+    /// * [`NodeError::InvalidNode`] if `parent` or `child` does not exist in this builder.
+    /// * [`NodeError::ExpectedGroup`] if `parent` is not a Union or Intersection. This is the
+    ///   same error [`expect_group`](Self::expect_group) returns, and serves as the invalid
+    ///   parent type error.
+    /// * [`NodeError::Cycle`] if `child` is `parent` or can reach it, which would otherwise be
+    ///   silently dropped by [`build`](Self::build).
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{ExpressionBuilder, builder::NodeError};
+    ///
+    /// let builder = ExpressionBuilder::<&str>::new();
+    /// let a = builder.set("A");
+    /// let group = builder.union([a]);
+    /// builder.add_root(group);
+    ///
+    /// let b = builder.set("B");
+    /// assert_eq!(builder.add_child(group, b), Ok(()));
+    /// assert_eq!(builder.add_child(a, b), Err(NodeError::ExpectedGroup));
+    ///
+    /// // a group can't contain itself, even through another node
+    /// let outer = builder.intersection([group]);
+    /// assert_eq!(builder.add_child(group, group), Err(NodeError::Cycle));
+    /// assert_eq!(builder.add_child(group, builder.not(outer)), Err(NodeError::Cycle));
+    ///
+    /// let expr = builder.build();
+    /// assert_eq!(expr.to_string(&expr.roots_owned()[0]), "([A] | [B])");
+    /// ```
+    pub fn add_child<H: Into<NodeHandle>>(
+        &self,
+        parent: NodeHandle,
+        child: H,
+    ) -> Result<(), NodeError> {
+        let child = child.into();
+        let mut nodes = self.nodes.borrow_mut();
+        if !nodes.contains_key(child) {
+            return Err(NodeError::InvalidNode);
+        }
+        match nodes.get(parent) {
+            Some(BuilderNode::Union(_) | BuilderNode::Intersection(_)) => {}
+            Some(_) => return Err(NodeError::ExpectedGroup),
+            None => return Err(NodeError::InvalidNode),
+        }
+
+        // walk down from the child, looking for the parent
+        let mut seen = SecondaryMap::new();
+        let mut stack = vec![child];
+        while let Some(handle) = stack.pop() {
+            if handle == parent {
+                return Err(NodeError::Cycle);
+            }
+            if seen.insert(handle, ()).is_some() {
+                continue;
+            }
+            match nodes.get(handle) {
+                Some(BuilderNode::Union(kids) | BuilderNode::Intersection(kids)) => {
+                    stack.extend(kids.iter().copied())
+                }
+                Some(BuilderNode::Not(kid)) => stack.push(*kid),
+                _ => {}
+            }
+        }
+
+        if let Some(BuilderNode::Union(kids) | BuilderNode::Intersection(kids)) =
+            nodes.get_mut(parent)
+        {
+            kids.push(child);
+        }
+        Ok(())
+    }
+
     /// Internal helper to force type errors to appear in user code.
     #[doc(hidden)]
     #[inline(always)]
@@ -210,19 +531,3 @@ impl<T> ExpressionBuilder<T> {
         self
     }
 }
-
-// TODO: re-implement this, and get ways to remove nodes and stuff
-// pub fn add_child(&mut self, parent: NodeHandle, child: NodeHandle) -> Result<(), NodeError> {
-// 	if let Some(node) = self.nodes.get_mut(parent) {
-// 		match node {
-// 			BuilderNode::Union(kids) |
-// 			BuilderNode::Intersection(kids) => {
-// 				kids.push(child);
-// 				Ok(())
-// 			},
-// 			_ => Err(NodeError::InvalidParentNodeType)
-// 		}
-// 	} else {
-// 		Err(NodeError::InvalidParentNode)
-// 	}
-// }
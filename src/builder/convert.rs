@@ -1,10 +1,10 @@
-use std::hash::Hash;
+use std::{cell::RefCell, hash::Hash};
 
 use slotmap::{SecondaryMap, SlotMap};
 
 use crate::{
-    builder::{BuilderNode, ExpressionBuilder, NodeHandle},
-    expr::{Expression, NodeId},
+    builder::{BuilderNode, ExpressionBuilder, MutBuilder, NodeHandle},
+    expr::{Expression, Node, NodeId},
 };
 
 impl<T: Hash + PartialEq> ExpressionBuilder<T> {
@@ -27,8 +27,124 @@ impl<T: Hash + PartialEq> ExpressionBuilder<T> {
     ///
     /// This allows you to append new roots to an existing structure without
     /// rebuilding the entire graph.
-    pub fn build_into(self, mut expr: Expression<T>) {
-        expr.extend(self);
+    ///
+    /// Returns the IDs of the newly added roots, in the order they were added to the builder.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{Expression, ExpressionBuilder};
+    ///
+    /// let mut expr = Expression::new();
+    /// let a = expr.set("A");
+    /// expr.add_root(a);
+    ///
+    /// let builder = ExpressionBuilder::new();
+    /// builder.add_root(builder.leaf("A") | builder.leaf("B"));
+    /// let new_roots = builder.build_into(&mut expr);
+    ///
+    /// assert_eq!(expr.root_count(), 2);
+    /// assert_eq!(expr.to_string(&new_roots[0]), "([A] | [B])");
+    /// ```
+    pub fn build_into<Meta: Default>(self, expr: &mut Expression<T, Meta>) -> Vec<NodeId> {
+        let nodes = self.nodes.into_inner();
+        let roots = self.roots.into_inner();
+        Self::stack_into(expr, nodes, &roots)
+    }
+}
+
+impl<T: Clone + Hash + PartialEq> ExpressionBuilder<T> {
+    /// Reconstructs a builder from a compiled `Expression`, keeping every root.
+    ///
+    /// Only live nodes are copied. Roots are re-added in their original order, with negated
+    /// roots wrapped in a `Not` node, so new roots can be appended before building again.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{ExpressionBuilder, eval::BoolEval};
+    ///
+    /// let builder = ExpressionBuilder::new();
+    /// let (admin, editor, viewer) = (builder.leaf("Admin"), builder.leaf("Editor"), builder.leaf("Viewer"));
+    /// let (banned, readonly) = (builder.leaf("Banned"), builder.leaf("ReadOnly"));
+    /// builder.add_root((admin | editor | viewer) & !banned);
+    /// builder.add_root((admin | editor) & !readonly & !banned);
+    /// builder.add_root(admin & !banned);
+    /// let rules = builder.build();
+    ///
+    /// // Round trip, adding a 4th rule
+    /// let builder = ExpressionBuilder::from_expression(&rules);
+    /// builder.add_root(!builder.leaf("Banned"));
+    /// let extended = builder.build();
+    /// assert_eq!(extended.root_count(), 4);
+    ///
+    /// for user in [vec!["Editor", "Banned"], vec!["Editor", "ReadOnly"], vec!["Admin"], vec![]] {
+    ///     let mut ctx = BoolEval::new();
+    ///     user.iter().for_each(|&k| ctx.add(k));
+    ///     let before = rules.evaluate(&mut ctx.clone()).unwrap();
+    ///     let after = extended.evaluate(&mut ctx).unwrap();
+    ///     assert_eq!(before[..], after[..3]);
+    /// }
+    /// ```
+    pub fn from_expression(expr: &Expression<T>) -> Self {
+        let mut nodes = SlotMap::with_key();
+        let (active, max_root) = expr.get_active();
+
+        // positive and negative handles for each expression node
+        let mut pos: Vec<Option<NodeHandle>> = vec![None; max_root + 1];
+        let mut neg: Vec<Option<NodeHandle>> = vec![None; max_root + 1];
+        pos[0] = Some(nodes.insert(BuilderNode::Empty));
+        neg[0] = Some(nodes.insert(BuilderNode::Universal));
+
+        // children always come before their parents
+        for idx in 1..=max_root {
+            if !active[idx] {
+                continue;
+            }
+            let node = match &expr.nodes[idx] {
+                Node::Empty => BuilderNode::Empty,
+                Node::Set(value) => BuilderNode::Set(value.clone()),
+                Node::Union(kids) => BuilderNode::Union(
+                    kids.iter()
+                        .map(|&k| handle_of(&mut nodes, &pos, &mut neg, k))
+                        .collect(),
+                ),
+                Node::Intersection(kids) => BuilderNode::Intersection(
+                    kids.iter()
+                        .map(|&k| handle_of(&mut nodes, &pos, &mut neg, k))
+                        .collect(),
+                ),
+            };
+            pos[idx] = Some(nodes.insert(node));
+        }
+
+        let roots = expr
+            .roots
+            .iter()
+            .map(|&root| handle_of(&mut nodes, &pos, &mut neg, root))
+            .collect();
+        Self {
+            nodes: RefCell::new(nodes),
+            roots: RefCell::new(roots),
+            ..Self::default()
+        }
+    }
+}
+
+impl<T: Hash + PartialEq> MutBuilder<T> {
+    /// Compiles the builder into an optimized `Expression`.
+    ///
+    /// Performs the same deduplication, pruning, and cycle removal as
+    /// [`ExpressionBuilder::build`].
+    pub fn build(self) -> Expression<T> {
+        let mut expr = Expression::new();
+        self.build_into(&mut expr);
+        expr
+    }
+
+    /// Compiles the builder and merges it into an existing `Expression`.
+    ///
+    /// Returns the IDs of the newly added roots, in the order they were added to the builder.
+    pub fn build_into<Meta: Default>(self, expr: &mut Expression<T, Meta>) -> Vec<NodeId> {
+        ExpressionBuilder::stack_into(expr, self.nodes, &self.roots)
     }
 }
 
@@ -40,7 +156,7 @@ impl<T> IntoIterator for ExpressionBuilder<T> {
     }
 }
 
-impl<T: Hash + PartialEq> Extend<ExpressionBuilder<T>> for Expression<T> {
+impl<T: Hash + PartialEq, Meta: Default> Extend<ExpressionBuilder<T>> for Expression<T, Meta> {
     fn extend<I: IntoIterator<Item = ExpressionBuilder<T>>>(&mut self, iter: I) {
         for source in iter {
             let nodes = source.nodes.into_inner();
@@ -61,7 +177,9 @@ impl<T> IntoIterator for &ExpressionBuilder<T> {
     }
 }
 
-impl<'a, T: Hash + PartialEq + Clone> Extend<&'a ExpressionBuilder<T>> for Expression<T> {
+impl<'a, T: Hash + PartialEq + Clone, Meta: Default> Extend<&'a ExpressionBuilder<T>>
+    for Expression<T, Meta>
+{
     fn extend<I: IntoIterator<Item = &'a ExpressionBuilder<T>>>(&mut self, iter: I) {
         for builder in iter {
             let nodes = builder.nodes.borrow().clone();
@@ -72,11 +190,12 @@ impl<'a, T: Hash + PartialEq + Clone> Extend<&'a ExpressionBuilder<T>> for Expre
 }
 
 impl<T: Hash + PartialEq> ExpressionBuilder<T> {
-    fn stack_into(
-        expr: &mut Expression<T>,
+    fn stack_into<Meta: Default>(
+        expr: &mut Expression<T, Meta>,
         mut nodes: SlotMap<NodeHandle, BuilderNode<T>>,
         roots: &[NodeHandle],
-    ) {
+    ) -> Vec<NodeId> {
+        let mut added = Vec::with_capacity(roots.len());
         let mut map = SecondaryMap::new();
         // tracks nodes on the stack, preventing loops
         let mut on_stack = SecondaryMap::new();
@@ -86,6 +205,7 @@ impl<T: Hash + PartialEq> ExpressionBuilder<T> {
             // check if already processed
             if let Some(&cached) = map.get(root) {
                 expr.add_root(cached);
+                added.push(cached);
                 continue;
             }
 
@@ -156,6 +276,22 @@ impl<T: Hash + PartialEq> ExpressionBuilder<T> {
             // add the root
             let final_root = map.get(root).copied().unwrap_or(NodeId::EMPTY);
             expr.add_root(final_root);
+            added.push(final_root);
         }
+        added
+    }
+}
+
+// negations share a single Not node
+fn handle_of<T>(
+    nodes: &mut SlotMap<NodeHandle, BuilderNode<T>>,
+    pos: &[Option<NodeHandle>],
+    neg: &mut [Option<NodeHandle>],
+    id: NodeId,
+) -> NodeHandle {
+    let positive = pos[id.idx()].unwrap();
+    if !id.is_neg() {
+        return positive;
     }
+    *neg[id.idx()].get_or_insert_with(|| nodes.insert(BuilderNode::Not(positive)))
 }
@@ -1,4 +1,4 @@
-use std::ops::{BitAnd, BitOr, Not};
+use std::ops::{BitAnd, BitOr, BitXor, Not};
 
 use crate::builder::{ExpressionBuilder, NodeHandle};
 
@@ -16,6 +16,31 @@ impl<'a, T> LogicNode<'a, T> {
     pub fn handle(&self) -> NodeHandle {
         self.handle
     }
+
+    /// Creates a material implication (`IF self THEN consequent`), stored as `!self | consequent`.
+    ///
+    /// See [`ExpressionBuilder::implies`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{ExpressionBuilder, NodeId};
+    ///
+    /// let builder = ExpressionBuilder::<&str>::new();
+    /// let a = builder.leaf("A");
+    /// builder.add_root(a.implies(builder.leaf("B")));
+    /// builder.add_root(a.implies(a));
+    /// let expr = builder.build();
+    ///
+    /// let roots: Vec<_> = expr.roots().collect();
+    /// assert_eq!(expr.to_string(roots[0]), "([A]' | [B])");
+    /// assert_eq!(*roots[1], NodeId::UNIVERSAL); // A -> A always holds
+    /// ```
+    pub fn implies(self, consequent: Self) -> Self {
+        Self {
+            builder: self.builder,
+            handle: self.builder.implies(self.handle, consequent.handle),
+        }
+    }
 }
 
 impl<'a, T> From<LogicNode<'a, T>> for NodeHandle {
@@ -34,18 +59,29 @@ impl<T> ExpressionBuilder<T> {
     /// Creates a `LogicNode` wrapper around a value.
     ///
     /// This creates a node in the builder and returns a helper struct that supports
-    /// operator overloading (`&`, `|`, `!`) for more ergonomic construction.
+    /// operator overloading (`&`, `|`, `^`, `!`) for more ergonomic construction.
     ///
     /// # Example
     /// ```rust
-    /// use logify::ExpressionBuilder;
+    /// use logify::{ExpressionBuilder, eval::BoolEval};
     ///
     /// let builder = ExpressionBuilder::<&str>::new();
     /// let a = builder.leaf("A");
     /// let b = builder.leaf("B");
+    /// let c = builder.leaf("C");
     ///
     /// // LogicNode supports standard operators
-    /// let c = a & !b;
+    /// let _ = a & !b;
+    ///
+    /// // Parity check: true when an odd number of inputs are set
+    /// builder.add_root(a ^ b ^ c);
+    /// let expr = builder.build();
+    ///
+    /// for (keys, odd) in [(vec!["A"], true), (vec!["A", "B"], false), (vec!["A", "B", "C"], true)] {
+    ///     let mut solver = BoolEval::new();
+    ///     keys.into_iter().for_each(|k| solver.add(k));
+    ///     assert_eq!(expr.evaluate(&mut solver), Ok(vec![odd]));
+    /// }
     /// ```
     pub fn leaf(&self, val: impl Into<T>) -> LogicNode<'_, T> {
         let h = self.set(val.into());
@@ -84,6 +120,21 @@ impl<'a, T> BitAnd for LogicNode<'a, T> {
     }
 }
 
+/// Exclusive or, built as `(A & !B) | (!A & B)`.
+impl<'a, T> BitXor for LogicNode<'a, T> {
+    type Output = LogicNode<'a, T>;
+
+    fn bitxor(self, rhs: Self) -> Self::Output {
+        let b = self.builder;
+        let left = b.intersection(vec![self.handle, b.not(rhs.handle)]);
+        let right = b.intersection(vec![b.not(self.handle), rhs.handle]);
+        LogicNode {
+            builder: b,
+            handle: b.union(vec![left, right]),
+        }
+    }
+}
+
 impl<'a, T> Not for LogicNode<'a, T> {
     type Output = LogicNode<'a, T>;
 
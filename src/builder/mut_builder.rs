@@ -0,0 +1,98 @@
+use slotmap::SlotMap;
+
+use crate::builder::{BuilderNode, NodeHandle};
+
+/// A builder that takes `&mut self`, skipping the `RefCell` borrow checks.
+///
+/// [`ExpressionBuilder`](crate::ExpressionBuilder) uses interior mutability so that
+/// `LogicNode` operators and the `logic!` macro can share it. When generating large
+/// expressions programmatically, those runtime checks are pure overhead; `MutBuilder`
+/// offers the same handle-based API without them.
+///
+/// # Example
+/// ```rust
+/// use logify::builder::MutBuilder;
+///
+/// let mut builder = MutBuilder::<&str>::new();
+///
+/// // (A | B) & !C
+/// let a = builder.set("A");
+/// let b = builder.set("B");
+/// let c = builder.set("C");
+/// let a_or_b = builder.union([a, b]);
+/// let not_c = builder.not(c);
+/// let root = builder.intersection([a_or_b, not_c]);
+/// builder.add_root(root);
+///
+/// let expr = builder.build();
+/// let root = expr.roots().next().unwrap();
+/// assert_eq!(expr.to_string(root), "(([A] | [B]) & [C]')");
+/// ```
+#[derive(Clone)]
+pub struct MutBuilder<T> {
+    pub nodes: SlotMap<NodeHandle, BuilderNode<T>>,
+    pub roots: Vec<NodeHandle>,
+}
+
+impl<T> Default for MutBuilder<T> {
+    fn default() -> Self {
+        Self {
+            nodes: SlotMap::with_key(),
+            roots: Vec::new(),
+        }
+    }
+}
+
+impl<T> MutBuilder<T> {
+    /// Creates a new, empty `MutBuilder`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a set (a leaf node) containing the given value.
+    pub fn set(&mut self, val: impl Into<T>) -> NodeHandle {
+        self.nodes.insert(BuilderNode::Set(val.into()))
+    }
+
+    /// Creates a constant Empty set node.
+    pub fn empty(&mut self) -> NodeHandle {
+        self.nodes.insert(BuilderNode::Empty)
+    }
+
+    /// Creates a constant Universal set node.
+    pub fn universal(&mut self) -> NodeHandle {
+        self.nodes.insert(BuilderNode::Universal)
+    }
+
+    /// Creates a Union (OR) node from the provided children.
+    pub fn union(&mut self, kids: impl IntoIterator<Item = NodeHandle>) -> NodeHandle {
+        let kids = kids.into_iter().collect();
+        self.nodes.insert(BuilderNode::Union(kids))
+    }
+
+    /// Creates an Intersection (AND) node from the provided children.
+    pub fn intersection(&mut self, kids: impl IntoIterator<Item = NodeHandle>) -> NodeHandle {
+        let kids = kids.into_iter().collect();
+        self.nodes.insert(BuilderNode::Intersection(kids))
+    }
+
+    /// Creates a Complement (NOT) node.
+    pub fn not(&mut self, child: NodeHandle) -> NodeHandle {
+        self.nodes.insert(BuilderNode::Not(child))
+    }
+
+    /// Marks a node as a "Root".
+    ///
+    /// Only nodes reachable from a root survive [`MutBuilder::build`].
+    pub fn add_root(&mut self, root: NodeHandle) {
+        self.roots.push(root);
+    }
+
+    /// Removes the root at `index`, returning its handle.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of range.
+    pub fn remove_root(&mut self, index: usize) -> NodeHandle {
+        self.roots.remove(index)
+    }
+}
@@ -1,10 +1,31 @@
-use crate::expr::{Expression, Node};
+use std::borrow::Borrow;
 
+use crate::expr::{Expression, Node, NodeId};
+
+#[cfg(feature = "bitset")]
+mod bitset_eval;
+#[cfg(feature = "bitset")]
+pub use bitset_eval::BitSetEval;
 mod bitwise_eval;
-pub use bitwise_eval::BitwiseEval;
+pub use bitwise_eval::{BitwiseEval, DefaultPolicy};
+mod bounded;
+pub use bounded::{Bounded, BoundedError};
 mod bool_eval;
 pub use bool_eval::BoolEval;
+mod convert;
+mod fuzzy_eval;
+pub use fuzzy_eval::FuzzyEval;
+mod kleene_eval;
+pub use kleene_eval::{Kleene, KleeneEval};
+mod retrying;
+pub use retrying::Retrying;
+#[cfg(feature = "roaring")]
+mod roaring_eval;
+#[cfg(feature = "roaring")]
+pub use roaring_eval::RoaringEval;
+mod stream;
 use serde::{Deserialize, Serialize};
+pub use stream::{ItemStream, StreamEvaluator};
 
 /// Defines how to resolve abstract logic into concrete results.
 ///
@@ -20,7 +41,8 @@ use serde::{Deserialize, Serialize};
 /// # Optimization Note
 /// This trait uses `eval_difference` instead of a direct `not` method. This allows implementations
 /// to avoid calculating "Everything except X" (which is often expensive or infinite) and instead
-/// implicitly calculate `A AND NOT B`.
+/// implicitly calculate `A AND NOT B`. Domains where negation is cheap (e.g., booleans) can
+/// also implement `eval_complement` to skip the Universal set entirely.
 pub trait Evaluator<T, R, E> {
     /// Returns the Universal Set (The set of all things).
     ///
@@ -29,6 +51,7 @@ pub trait Evaluator<T, R, E> {
     ///
     /// If your domain does not support a "Universal" set (e.g., an infinite number line),
     /// you can return an error here, but be aware that top-level negations will fail.
+    /// [`Expression::needs_universal`] reports this ahead of evaluation.
     fn get_universal(&mut self) -> Result<R, E>; // TODO: Might not be useful
 
     /// Returns the Empty Set (The set of nothing).
@@ -54,6 +77,24 @@ pub trait Evaluator<T, R, E> {
         I: IntoIterator<Item = &'a R>,
         I::IntoIter: ExactSizeIterator;
 
+    /// Like [`eval_union`](Self::eval_union), but with the first operand given by value.
+    ///
+    /// Called when `first` will not be read again (see [`Expression::evaluate_with_pruning`]),
+    /// so implementations can merge `rest` into it in place instead of cloning it.
+    ///
+    /// The default passes everything to `eval_union` and drops `first`.
+    fn eval_union_owned<'a, I>(&mut self, first: R, rest: I) -> Result<R, E>
+    where
+        R: 'a,
+        I: IntoIterator<Item = &'a R>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        self.eval_union(Prepend {
+            first: Some(&first),
+            rest: rest.into_iter(),
+        })
+    }
+
     /// Filters multiple results via an Intersection (AND) operation.
     ///
     /// # Arguments
@@ -67,6 +108,25 @@ pub trait Evaluator<T, R, E> {
         I: IntoIterator<Item = &'a R>,
         I::IntoIter: ExactSizeIterator;
 
+    /// Like [`eval_intersection`](Self::eval_intersection), but with the first operand given
+    /// by value.
+    ///
+    /// Called when `first` will not be read again (see [`Expression::evaluate_with_pruning`]),
+    /// so implementations can filter it in place instead of cloning it.
+    ///
+    /// The default passes everything to `eval_intersection` and drops `first`.
+    fn eval_intersection_owned<'a, I>(&mut self, first: R, rest: I) -> Result<R, E>
+    where
+        R: 'a,
+        I: IntoIterator<Item = &'a R>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        self.eval_intersection(Prepend {
+            first: Some(&first),
+            rest: rest.into_iter(),
+        })
+    }
+
     /// Calculates the difference between two results (`Include AND NOT Exclude`).
     ///
     /// This is used to handle negation. The expression engine transforms negations
@@ -79,6 +139,213 @@ pub trait Evaluator<T, R, E> {
     /// * `include` - The base set of items.
     /// * `exclude` - The set of items to remove from the base set.
     fn eval_difference(&mut self, include: &R, exclude: &R) -> Result<R, E>;
+
+    /// Calculates the complement (`NOT value`) directly, if supported.
+    ///
+    /// Pure negations (e.g., `!A` or `!A & !B`) are otherwise computed as
+    /// `eval_difference(Universal, ...)`, which requires materializing the Universal set.
+    /// Return `Ok(Some(_))` if your domain can negate a result more cheaply.
+    ///
+    /// The default returns `Ok(None)`, falling back to the difference.
+    fn eval_complement(&mut self, _value: &R) -> Result<Option<R>, E> {
+        Ok(None)
+    }
+
+    /// Calculates `NOT (A OR B OR ...)` directly, if supported.
+    ///
+    /// Called first for intersections made only of exclusions (`!A & !B`). Return
+    /// `Ok(Some(_))` if your domain has a native NOR, so neither the union nor the Universal
+    /// set is needed.
+    ///
+    /// The default returns `Ok(None)`, after which the excludes are combined with
+    /// `eval_union` and passed to [`eval_complement`](Self::eval_complement), falling back to
+    /// `eval_difference(Universal, ...)` if that is unsupported too.
+    fn eval_nor<'a, I>(&mut self, _excludes: I) -> Result<Option<R>, E>
+    where
+        R: 'a,
+        I: IntoIterator<Item = &'a R>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        Ok(None)
+    }
+
+    /// Estimates the size of a result, used to order the operands of an intersection.
+    ///
+    /// When any operand of an intersection has a non-zero estimate, the operands are passed to
+    /// [`eval_intersection`](Self::eval_intersection) smallest first, so the running result
+    /// shrinks as early as possible. Ties keep their stored order.
+    ///
+    /// The default returns `0`, which leaves the order unchanged.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{Evaluator, Expression};
+    /// use std::collections::HashMap;
+    ///
+    /// struct Ids {
+    ///     sets: HashMap<&'static str, Vec<u32>>,
+    ///     order: Vec<usize>,
+    /// }
+    ///
+    /// impl Evaluator<&'static str, Vec<u32>, ()> for Ids {
+    ///     fn get_universal(&mut self) -> Result<Vec<u32>, ()> { Err(()) }
+    ///     fn get_empty(&mut self) -> Result<Vec<u32>, ()> { Ok(vec![]) }
+    ///     fn eval_set(&mut self, set: &&'static str) -> Result<Vec<u32>, ()> {
+    ///         Ok(self.sets.get(set).cloned().unwrap_or_default())
+    ///     }
+    ///     fn eval_union<'a, I>(&mut self, values: I) -> Result<Vec<u32>, ()>
+    ///     where I: IntoIterator<Item = &'a Vec<u32>>, I::IntoIter: ExactSizeIterator {
+    ///         let mut out: Vec<u32> = values.into_iter().flatten().copied().collect();
+    ///         out.sort();
+    ///         out.dedup();
+    ///         Ok(out)
+    ///     }
+    ///     fn eval_intersection<'a, I>(&mut self, values: I) -> Result<Vec<u32>, ()>
+    ///     where I: IntoIterator<Item = &'a Vec<u32>>, I::IntoIter: ExactSizeIterator {
+    ///         let mut iter = values.into_iter();
+    ///         let mut out = iter.next().unwrap().clone();
+    ///         self.order = vec![out.len()];
+    ///         for v in iter {
+    ///             self.order.push(v.len());
+    ///             out.retain(|x| v.contains(x));
+    ///         }
+    ///         Ok(out)
+    ///     }
+    ///     fn eval_difference(&mut self, inc: &Vec<u32>, exc: &Vec<u32>) -> Result<Vec<u32>, ()> {
+    ///         Ok(inc.iter().filter(|x| !exc.contains(x)).copied().collect())
+    ///     }
+    ///     fn estimate_size(&self, value: &Vec<u32>) -> usize {
+    ///         value.len()
+    ///     }
+    /// }
+    ///
+    /// let mut expr = Expression::new();
+    /// let leaves = ["Big", "Small", "Medium"].map(|t| expr.set(t));
+    /// let root = expr.intersection(leaves);
+    /// expr.add_root(root);
+    ///
+    /// let mut ids = Ids { sets: HashMap::new(), order: vec![] };
+    /// ids.sets.insert("Big", (0..1000).collect());
+    /// ids.sets.insert("Medium", (0..100).collect());
+    /// ids.sets.insert("Small", vec![3, 7]);
+    ///
+    /// assert_eq!(expr.evaluate(&mut ids), Ok(vec![vec![3, 7]]));
+    /// assert_eq!(ids.order, [2, 100, 1000]);
+    /// ```
+    fn estimate_size(&self, _value: &R) -> usize {
+        0
+    }
+}
+
+/// Classifies evaluation errors for decorators such as [`Retrying`].
+///
+/// Implement this for your `Evaluator`'s error type to mark transient failures
+/// (timeouts, dropped connections) as safe to retry.
+pub trait EvalError {
+    /// Returns `true` if the failed operation may succeed when attempted again.
+    ///
+    /// The default treats every error as permanent.
+    fn is_retryable(&self) -> bool {
+        false
+    }
+}
+
+impl EvalError for () {}
+
+// yields `first` ahead of `rest`, for the default owned evaluator methods
+struct Prepend<'a, R, I> {
+    first: Option<&'a R>,
+    rest: I,
+}
+
+impl<'a, 'b: 'a, R: 'b, I: ExactSizeIterator<Item = &'b R>> Iterator for Prepend<'a, R, I> {
+    type Item = &'a R;
+
+    fn next(&mut self) -> Option<&'a R> {
+        self.first.take().or_else(|| self.rest.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.first.is_some() as usize + self.rest.len();
+        (len, Some(len))
+    }
+}
+
+impl<'a, 'b: 'a, R: 'b, I: ExactSizeIterator<Item = &'b R>> ExactSizeIterator
+    for Prepend<'a, R, I>
+{
+}
+
+/// Storage for intermediate results during evaluation.
+///
+/// The evaluation loop reads and writes results through this trait, so results can be kept
+/// somewhere other than the heap, such as a slab allocator or memory-mapped files.
+/// See [`Expression::evaluate_with_storage`].
+///
+/// # Slots
+/// Node `i` stores its positive result in slot `2 * i` and its negation in slot `2 * i + 1`.
+/// Slot `1` holds the Universal set. Slots may be written in any order, so `set` must accept
+/// indices past the current end of the store.
+pub trait ResultStore<R> {
+    /// Returns the result in `idx`, if one has been stored.
+    fn get(&self, idx: usize) -> Option<&R>;
+
+    /// Stores `value` in `idx`, replacing any previous result.
+    fn set(&mut self, idx: usize, value: R);
+
+    /// Removes and returns the result in `idx`, if one has been stored.
+    fn take(&mut self, idx: usize) -> Option<R>;
+}
+
+// a borrowed node, so evaluation can read groups from any storage layout
+pub(crate) enum NodeRef<'a, T> {
+    Empty,
+    Set(&'a T),
+    Union(&'a [NodeId]),
+    Intersection(&'a [NodeId]),
+}
+
+// a node list in topological order, as evaluation reads it
+pub(crate) trait NodeList<T> {
+    fn node_count(&self) -> usize;
+    fn node(&self, idx: usize) -> NodeRef<'_, T>;
+}
+
+impl<T> NodeList<T> for [Node<T>] {
+    #[inline]
+    fn node_count(&self) -> usize {
+        self.len()
+    }
+
+    #[inline]
+    fn node(&self, idx: usize) -> NodeRef<'_, T> {
+        match &self[idx] {
+            Node::Empty => NodeRef::Empty,
+            Node::Set(set) => NodeRef::Set(set),
+            Node::Union(kids) => NodeRef::Union(kids),
+            Node::Intersection(kids) => NodeRef::Intersection(kids),
+        }
+    }
+}
+
+impl<R> ResultStore<R> for Vec<Option<R>> {
+    #[inline]
+    fn get(&self, idx: usize) -> Option<&R> {
+        self.as_slice().get(idx).and_then(|r| r.as_ref())
+    }
+
+    #[inline]
+    fn set(&mut self, idx: usize, value: R) {
+        if idx >= self.len() {
+            self.resize_with(idx + 1, || None);
+        }
+        self[idx] = Some(value);
+    }
+
+    #[inline]
+    fn take(&mut self, idx: usize) -> Option<R> {
+        self.get_mut(idx).and_then(|r| r.take())
+    }
 }
 
 /// A reusable memory buffer for expression evaluation.
@@ -138,6 +405,10 @@ pub trait Evaluator<T, R, E> {
 #[derive(Serialize, Deserialize)]
 pub struct EvaluatorCache<R> {
     pub(crate) cache: Vec<Option<R>>,
+    // scratch buffer for the live-node scan, only kept to reuse its allocation
+    #[serde(skip)]
+    #[cfg_attr(feature = "fast-binary", bitcode(skip))]
+    pub(crate) active: Vec<bool>,
     pub(crate) include_indices: Vec<usize>,
     pub(crate) exclude_indices: Vec<usize>,
     pub(crate) expr_uuid: u128, // 0 for an uninitialized cache
@@ -147,6 +418,7 @@ impl<R> Default for EvaluatorCache<R> {
     fn default() -> Self {
         Self {
             cache: Vec::new(),
+            active: Vec::new(),
             include_indices: Vec::new(),
             exclude_indices: Vec::new(),
             expr_uuid: 0,
@@ -165,11 +437,70 @@ impl<R> EvaluatorCache<R> {
     /// Usually not necessary, as `evaluate_with` handles invalidation automatically.
     pub fn clear(&mut self) {
         self.cache.clear();
+        self.active.clear();
+        self.include_indices.clear();
+        self.exclude_indices.clear();
         self.expr_uuid = 0; // mark as uninitialized
     }
 }
 
-impl<T> Expression<T> {
+impl<R> ResultStore<R> for EvaluatorCache<R> {
+    fn get(&self, idx: usize) -> Option<&R> {
+        ResultStore::get(&self.cache, idx)
+    }
+
+    fn set(&mut self, idx: usize, value: R) {
+        self.cache.set(idx, value)
+    }
+
+    fn take(&mut self, idx: usize) -> Option<R> {
+        self.cache.take(idx)
+    }
+}
+
+/// Evaluates a stream of expressions against one solver, reusing a single cache.
+///
+/// Each expression has its own UUID, so the cache resets between them, but its buffers keep
+/// their capacity. This avoids allocating a fresh cache per expression in batch jobs.
+/// Results are produced lazily as the iterator is consumed.
+///
+/// # Example
+/// ```rust
+/// use logify::{ExpressionBuilder, eval::{BoolEval, evaluate_many}};
+///
+/// let rules: Vec<_> = ["A", "B", "C"]
+///     .into_iter()
+///     .map(|term| {
+///         let builder = ExpressionBuilder::new();
+///         builder.add_root(builder.leaf(term));
+///         builder.build()
+///     })
+///     .collect();
+///
+/// let mut ctx = BoolEval::new();
+/// ctx.add("A");
+/// ctx.add("C");
+///
+/// let results: Result<Vec<_>, _> = evaluate_many(&rules, &mut ctx).collect();
+/// assert_eq!(results, Ok(vec![vec![true], vec![false], vec![true]]));
+/// ```
+pub fn evaluate_many<T, R, E, S, I>(
+    exprs: I,
+    solver: &mut S,
+) -> impl Iterator<Item = Result<Vec<R>, E>>
+where
+    R: Clone,
+    S: Evaluator<T, R, E>,
+    I: IntoIterator,
+    I::Item: Borrow<Expression<T>>,
+{
+    let mut cache = EvaluatorCache::new();
+    exprs
+        .into_iter()
+        .map(move |expr| expr.borrow().evaluate_with(solver, &mut cache))
+}
+
+impl<T, Meta> Expression<T, Meta> {
     /// Evaluates the expression using a temporary cache.
     ///
     /// This is a convenience wrapper around [`evaluate_with`](Self::evaluate_with).
@@ -205,6 +536,233 @@ impl<T> Expression<T> {
         solver: &mut S,
         cache: &mut EvaluatorCache<R>,
     ) -> Result<Vec<R>, E>
+    where
+        R: Clone,
+        S: Evaluator<T, R, E>,
+    {
+        self.fill_cache(solver, cache)?;
+        Self::collect_roots(&self.roots, solver, &mut cache.cache)
+    }
+
+    /// Checks whether evaluation may ask the solver for the Universal set.
+    ///
+    /// This is true when a root is negated, a union holds a negated child, or an intersection
+    /// is made only of negated children. It assumes the solver has no native
+    /// [`eval_complement`](Evaluator::eval_complement); one that does never needs the
+    /// Universal set, and one with [`eval_nor`](Evaluator::eval_nor) avoids it for pure
+    /// exclusions. Call this before evaluating to reject expressions that an infinite-domain
+    /// solver cannot answer, rather than failing partway through.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::ExpressionBuilder;
+    ///
+    /// let builder = ExpressionBuilder::<&str>::new();
+    /// builder.add_root(builder.leaf("A") & !builder.leaf("B")); // A - B
+    /// assert!(!builder.clone().build().needs_universal());
+    ///
+    /// builder.add_root(!builder.leaf("C"));
+    /// assert!(builder.build().needs_universal());
+    /// ```
+    pub fn needs_universal(&self) -> bool {
+        if self.roots.iter().any(|r| r.is_neg()) {
+            return true;
+        }
+        let (active, _) = self.get_active();
+        self.nodes
+            .iter()
+            .zip(active)
+            .filter(|(_, active)| *active)
+            .any(|(node, _)| match node {
+                Node::Union(kids) => kids.iter().any(|k| k.is_neg()),
+                Node::Intersection(kids) => !kids.is_empty() && kids.iter().all(|k| k.is_neg()),
+                _ => false,
+            })
+    }
+
+    /// Checks whether an [`EvaluatorCache`] filled for `other` is valid for `self`.
+    ///
+    /// Cache slots are addressed by node index, so a cache carries over exactly when both
+    /// expressions store the same nodes in the same order, as with two copies of one template
+    /// (even with different roots or metadata). Every node is compared, so this is linear in
+    /// the size of the expressions.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::ExpressionBuilder;
+    ///
+    /// let template = || {
+    ///     let builder = ExpressionBuilder::<&str>::new();
+    ///     builder.add_root(builder.leaf("A") & !builder.leaf("B"));
+    ///     builder.build()
+    /// };
+    /// let (x, y) = (template(), template());
+    /// assert!(x.cache_compatible(&y));
+    ///
+    /// let builder = ExpressionBuilder::<&str>::new();
+    /// builder.add_root(builder.leaf("A") | builder.leaf("B"));
+    /// assert!(!x.cache_compatible(&builder.build()));
+    /// ```
+    pub fn cache_compatible<M2>(&self, other: &Expression<T, M2>) -> bool
+    where
+        T: PartialEq,
+    {
+        self.uuid == other.uuid || self.nodes == other.nodes
+    }
+
+    /// Evaluates with a cache that may have been filled for another, compatible expression.
+    ///
+    /// If `cache` belongs to `owner` and the two are [`cache_compatible`](Self::cache_compatible),
+    /// the cache is handed over to `self` with its buffers and stored results intact, despite
+    /// the different UUIDs. Otherwise this behaves exactly like
+    /// [`evaluate_with`](Self::evaluate_with), resetting the cache.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{EvaluatorCache, ExpressionBuilder, eval::BoolEval};
+    ///
+    /// let template = || {
+    ///     let builder = ExpressionBuilder::<&str>::new();
+    ///     builder.add_root(builder.leaf("A") & !builder.leaf("B"));
+    ///     builder.build()
+    /// };
+    /// let (x, y) = (template(), template());
+    ///
+    /// let mut solver = BoolEval::new();
+    /// solver.add("A");
+    /// let mut cache = EvaluatorCache::new();
+    /// assert_eq!(x.evaluate_with(&mut solver, &mut cache), Ok(vec![true]));
+    ///
+    /// // y reuses the results x stored, so the new solver is never consulted
+    /// let mut unused = BoolEval::new();
+    /// assert_eq!(y.evaluate_with_shared_cache(&x, &mut unused, &mut cache), Ok(vec![true]));
+    ///
+    /// // a plain evaluate_with would have reset the cache
+    /// assert_eq!(x.evaluate_with(&mut unused, &mut cache), Ok(vec![false]));
+    /// ```
+    pub fn evaluate_with_shared_cache<R, E, S, M2>(
+        &self,
+        owner: &Expression<T, M2>,
+        solver: &mut S,
+        cache: &mut EvaluatorCache<R>,
+    ) -> Result<Vec<R>, E>
+    where
+        T: PartialEq,
+        R: Clone,
+        S: Evaluator<T, R, E>,
+    {
+        if cache.expr_uuid == owner.uuid && self.cache_compatible(owner) {
+            cache.expr_uuid = self.uuid;
+        }
+        self.evaluate_with(solver, cache)
+    }
+
+    /// Evaluates a single-root expression, moving the result out of the cache.
+    ///
+    /// Unlike [`evaluate_with`](Self::evaluate_with), this neither allocates a `Vec` nor
+    /// clones the result, which matters for large result types such as bitmaps. Intermediate
+    /// results stay in `cache`, but the root's own result is taken, so it will be recomputed
+    /// (from the cached children) on the next call.
+    ///
+    /// # Panics
+    /// Panics if the expression does not have exactly one root.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{EvaluatorCache, ExpressionBuilder, eval::BoolEval};
+    ///
+    /// let builder = ExpressionBuilder::new();
+    /// let a = builder.leaf("A");
+    /// let b = builder.leaf("B");
+    /// builder.add_root(a & !b);
+    /// let expr = builder.build();
+    ///
+    /// let mut solver = BoolEval::new();
+    /// solver.add("A");
+    ///
+    /// let mut cache = EvaluatorCache::new();
+    /// assert_eq!(expr.evaluate_single_with(&mut solver, &mut cache), Ok(true));
+    /// ```
+    pub fn evaluate_single_with<R, E, S>(
+        &self,
+        solver: &mut S,
+        cache: &mut EvaluatorCache<R>,
+    ) -> Result<R, E>
+    where
+        R: Clone,
+        S: Evaluator<T, R, E>,
+    {
+        if self.roots.len() != 1 {
+            panic!(
+                "evaluate_single_with requires exactly one root, but the expression has {} roots.",
+                self.roots.len(),
+            );
+        }
+        self.fill_cache(solver, cache)?;
+        let store = &mut cache.cache;
+
+        // the root positive is now in the store
+        let root = self.roots[0];
+        if let Some(res) = store.take(root.raw() as usize) {
+            return Ok(res);
+        }
+        if root.raw() == 1 {
+            return solver.get_universal();
+        }
+        Self::negate(solver, store, root.idx() << 1)
+    }
+
+    /// Evaluates the expression, storing intermediate results in a caller-owned store.
+    ///
+    /// This lets results live outside of an [`EvaluatorCache`], such as in a slab allocator,
+    /// a custom pool, or memory-mapped files. Results are addressed by slot: node `i` stores its
+    /// positive result in slot `2 * i` and its negation in slot `2 * i + 1`.
+    ///
+    /// # Important
+    /// Unlike `evaluate_with`, the store is not validated. It must be empty, or only hold
+    /// results previously computed for this same expression.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{ExpressionBuilder, eval::{BoolEval, ResultStore}};
+    ///
+    /// let builder = ExpressionBuilder::new();
+    /// builder.add_root(builder.leaf("A") | builder.leaf("B"));
+    /// let expr = builder.build();
+    ///
+    /// let mut solver = BoolEval::new();
+    /// solver.add("B");
+    ///
+    /// // Any ResultStore works, a plain Vec<Option<R>> included
+    /// let mut store: Vec<Option<bool>> = Vec::new();
+    /// assert_eq!(expr.evaluate_with_storage(&mut solver, &mut store), Ok(vec![true]));
+    /// ```
+    pub fn evaluate_with_storage<R, E, S>(
+        &self,
+        solver: &mut S,
+        store: &mut dyn ResultStore<R>,
+    ) -> Result<Vec<R>, E>
+    where
+        R: Clone,
+        S: Evaluator<T, R, E>,
+    {
+        let mut active = Vec::new();
+        let mut include_indices = Vec::new();
+        let mut exclude_indices = Vec::new();
+        Self::fill_store(
+            self.nodes.as_slice(),
+            &self.roots,
+            solver,
+            store,
+            &mut active,
+            &mut include_indices,
+            &mut exclude_indices,
+        )?;
+        Self::collect_roots(&self.roots, solver, store)
+    }
+
+    // validates the cache and computes the positive result of every root
+    fn fill_cache<R, E, S>(&self, solver: &mut S, cache: &mut EvaluatorCache<R>) -> Result<(), E>
     where
         R: Clone,
         S: Evaluator<T, R, E>,
@@ -216,17 +774,45 @@ impl<T> Expression<T> {
         }
 
         // load cache
-        let cache_vec = &mut cache.cache;
-        if cache_vec.len() < self.nodes.len() * 2 {
-            cache_vec.resize(self.nodes.len() * 2, None);
+        // nodes are append-only, so a matching cache (possibly loaded from disk) may be shorter
+        // than the expression, but every stored slot remains valid
+        if cache.cache.len() < self.nodes.len() * 2 {
+            cache.cache.resize(self.nodes.len() * 2, None);
         }
 
+        Self::fill_store(
+            self.nodes.as_slice(),
+            &self.roots,
+            solver,
+            &mut cache.cache,
+            &mut cache.active,
+            &mut cache.include_indices,
+            &mut cache.exclude_indices,
+        )
+    }
+
+    // computes the positive result of every root that isn't already stored
+    pub(crate) fn fill_store<R, E, S, St, N>(
+        nodes: &N,
+        roots: &[NodeId],
+        solver: &mut S,
+        store: &mut St,
+        active: &mut Vec<bool>,
+        include_indices: &mut Vec<usize>,
+        exclude_indices: &mut Vec<usize>,
+    ) -> Result<(), E>
+    where
+        S: Evaluator<T, R, E>,
+        St: ResultStore<R> + ?Sized,
+        N: NodeList<T> + ?Sized,
+    {
         // initialize active nodes with the roots to find
         let mut max_root = 0; // furthest root location, node 0 has no children, so safe as a flag to avoid finding children
-        let mut active = vec![false; self.nodes.len()];
-        for root in &self.roots {
+        active.clear();
+        active.resize(nodes.node_count(), false);
+        for root in roots {
             // skip over already loaded roots
-            if cache_vec[root.idx() << 1].is_none() {
+            if store.get(root.idx() << 1).is_none() {
                 active[root.idx()] = true;
                 if root.idx() > max_root {
                     max_root = root.idx();
@@ -236,13 +822,13 @@ impl<T> Expression<T> {
 
         // finds all children of uncomputed roots
         if max_root != 0 {
-            for idx in (0..self.nodes.len()).rev() {
+            for idx in (0..nodes.node_count()).rev() {
                 if !active[idx] {
                     continue;
                 } // dead node
                 // activate all children
-                match &self.nodes[idx] {
-                    Node::Union(kids) | Node::Intersection(kids) => {
+                match nodes.node(idx) {
+                    NodeRef::Union(kids) | NodeRef::Intersection(kids) => {
                         for k in kids {
                             active[k.idx()] = true;
                         }
@@ -253,45 +839,56 @@ impl<T> Expression<T> {
         }
 
         // evaluate each node
-        for (idx, node) in self.nodes.iter().enumerate() {
+        for (idx, &live) in active.iter().enumerate() {
             if idx > max_root {
                 break;
             } // only evaluate up to the last needed root
-            if !active[idx] {
+            if !live {
                 continue;
             } // skips non-active nodes
-            if cache_vec[idx << 1].is_some() {
+            if store.get(idx << 1).is_some() {
                 continue;
             } // already evaluated
 
             // node must be calculated
             let result = Self::evaluate_node(
-                node,
+                nodes.node(idx),
                 solver,
-                cache_vec,
-                &mut cache.include_indices,
-                &mut cache.exclude_indices,
+                store,
+                include_indices,
+                exclude_indices,
+                |_| false,
             )?;
-            cache_vec[idx << 1] = Some(result);
+            store.set(idx << 1, result);
         }
+        Ok(())
+    }
 
-        // all root positives are now in cache
-        let mut results = Vec::with_capacity(self.roots.len());
-        for root in &self.roots {
-            if let Some(res) = &cache_vec[root.raw() as usize] {
+    // gathers the results of all roots, computing negations that are still missing
+    pub(crate) fn collect_roots<R, E, S, St>(
+        roots: &[NodeId],
+        solver: &mut S,
+        store: &mut St,
+    ) -> Result<Vec<R>, E>
+    where
+        R: Clone,
+        S: Evaluator<T, R, E>,
+        St: ResultStore<R> + ?Sized,
+    {
+        // all root positives are now in the store
+        let mut results = Vec::with_capacity(roots.len());
+        for root in roots {
+            if let Some(res) = store.get(root.raw() as usize) {
                 results.push(res.clone());
             } else {
-                if cache_vec[1].is_none() {
-                    cache_vec[1] = Some(solver.get_universal()?);
-                }
-                let uni = cache_vec[1].as_ref().unwrap();
+                // root not in store, must be negative and positive must be in store
                 if root.raw() == 1 {
-                    results.push(uni.clone());
+                    Self::load_universal(solver, store)?;
+                    results.push(store.get(1).unwrap().clone());
                 } else {
-                    let pos = cache_vec[root.idx() << 1].as_ref().unwrap();
-                    let neg = solver.eval_difference(uni, pos)?;
-                    cache_vec[root.raw() as usize] = Some(neg.clone());
-                    results.push(neg);
+                    let neg = Self::negate(solver, store, root.idx() << 1)?;
+                    results.push(neg.clone());
+                    store.set(root.raw() as usize, neg);
                 }
             }
         }
@@ -338,7 +935,8 @@ impl<T> Expression<T> {
         }
 
         // traverse the expression linearly
-        for (idx, node) in self.nodes.iter().enumerate() {
+        let nodes = self.nodes.as_slice();
+        for (idx, node) in nodes.iter().enumerate() {
             if counts[idx] == 0 {
                 continue;
             } // node isn't used
@@ -347,12 +945,14 @@ impl<T> Expression<T> {
             } // already evaluated
 
             // node must be calculated
+            // a child with one parent left is only read by this node, so its result can be moved
             let result = Self::evaluate_node(
-                node,
+                nodes.node(idx),
                 solver,
                 &mut cache,
                 &mut include_indices,
                 &mut exclude_indices,
+                |slot| counts[slot >> 1] == 1,
             )?;
             cache[idx << 1] = Some(result);
 
@@ -371,73 +971,117 @@ impl<T> Expression<T> {
             }
         }
 
-        // all root positives are now in cache
-        let mut results = Vec::with_capacity(self.roots.len());
-        for root in &self.roots {
-            if let Some(res) = &cache[root.raw() as usize] {
-                // root in cache
-                results.push(res.clone());
-            } else {
-                // root not in cache, must be negative and positive must be in cache
-                if cache[1].is_none() {
-                    cache[1] = Some(solver.get_universal()?);
-                }
-                let uni = cache[1].as_ref().unwrap();
-                if root.raw() == 1 {
-                    results.push(uni.clone());
-                } else {
-                    let pos = cache[root.idx() << 1].as_ref().unwrap();
-                    let neg = solver.eval_difference(uni, pos)?;
-                    cache[root.raw() as usize] = Some(neg.clone());
-                    results.push(neg);
-                }
-            }
+        Self::collect_roots(&self.roots, solver, &mut cache)
+    }
+
+    // makes sure the universal set is stored in slot 1
+    #[inline]
+    fn load_universal<R, E, S, St>(solver: &mut S, store: &mut St) -> Result<(), E>
+    where
+        S: Evaluator<T, R, E>,
+        St: ResultStore<R> + ?Sized,
+    {
+        if store.get(1).is_none() {
+            store.set(1, solver.get_universal()?);
         }
-        Ok(results)
+        Ok(())
+    }
+
+    // computes the negation of the result in slot `idx`, avoiding the universal set if possible
+    #[inline]
+    fn negate<R, E, S, St>(solver: &mut S, store: &mut St, idx: usize) -> Result<R, E>
+    where
+        S: Evaluator<T, R, E>,
+        St: ResultStore<R> + ?Sized,
+    {
+        if let Some(neg) = solver.eval_complement(store.get(idx).unwrap())? {
+            return Ok(neg);
+        }
+        Self::load_universal(solver, store)?;
+        solver.eval_difference(store.get(1).unwrap(), store.get(idx).unwrap())
     }
 
+    // moves out the first result in `indices` that `reusable` allows, removing it from `indices`
     #[inline]
-    fn evaluate_node<R, E, S>(
-        node: &Node<T>,
+    fn take_reusable<R, St>(
+        store: &mut St,
+        indices: &mut Vec<usize>,
+        reusable: &impl Fn(usize) -> bool,
+    ) -> Option<R>
+    where
+        St: ResultStore<R> + ?Sized,
+    {
+        let pos = indices.iter().position(|&i| reusable(i))?;
+        let value = store.take(indices[pos])?;
+        indices.remove(pos);
+        Some(value)
+    }
+
+    // intersects the results in `indices`, moving one of them out if `reusable` allows
+    #[inline]
+    fn intersect<R, E, S, St>(
+        solver: &mut S,
+        store: &mut St,
+        indices: &mut Vec<usize>,
+        reusable: &impl Fn(usize) -> bool,
+    ) -> Result<R, E>
+    where
+        S: Evaluator<T, R, E>,
+        St: ResultStore<R> + ?Sized,
+    {
+        match Self::take_reusable(store, indices, reusable) {
+            Some(first) => solver
+                .eval_intersection_owned(first, indices.iter().map(|&i| store.get(i).unwrap())),
+            None => solver.eval_intersection(indices.iter().map(|&i| store.get(i).unwrap())),
+        }
+    }
+
+    // `reusable` reports slots that no later node reads, which are passed to the solver by value
+    #[inline]
+    fn evaluate_node<R, E, S, St>(
+        node: NodeRef<'_, T>,
         solver: &mut S,
-        cache_vec: &mut [Option<R>],
+        store: &mut St,
         include_indices: &mut Vec<usize>,
         exclude_indices: &mut Vec<usize>,
+        reusable: impl Fn(usize) -> bool,
     ) -> Result<R, E>
     where
-        R: Clone,
         S: Evaluator<T, R, E>,
+        St: ResultStore<R> + ?Sized,
     {
         match node {
-            Node::Empty => Ok(solver.get_empty()?),
-            Node::Set(set) => Ok(solver.eval_set(set)?),
-            Node::Union(kids) => {
+            NodeRef::Empty => Ok(solver.get_empty()?),
+            NodeRef::Set(set) => Ok(solver.eval_set(set)?),
+            NodeRef::Union(kids) => {
                 // make sure all negated terms are calculated
-                let (uni_cache, other_cache) = cache_vec.split_at_mut(2);
                 for k in kids {
-                    let idx = k.raw() as usize - 2;
-                    let pos_idx = (k.idx() << 1) - 2;
-                    if other_cache[idx].is_none() {
+                    if store.get(k.raw() as usize).is_none() {
                         // must be negative
-                        let uni = uni_cache[1].get_or_insert(solver.get_universal()?);
-                        let pos = other_cache[pos_idx].as_ref().unwrap();
-                        let neg = solver.eval_difference(uni, pos)?;
-                        other_cache[idx] = Some(neg); // add negative to cache
+                        let neg = Self::negate(solver, store, k.idx() << 1)?;
+                        store.set(k.raw() as usize, neg); // add negative to store
                     }
                 }
                 // evaluate the union
-                Ok(solver.eval_union(
-                    kids.iter()
-                        .map(|k| cache_vec[k.raw() as usize].as_ref().unwrap()),
-                )?)
+                include_indices.clear();
+                include_indices.extend(kids.iter().map(|k| k.raw() as usize));
+                match Self::take_reusable(store, include_indices, &reusable) {
+                    Some(first) => solver.eval_union_owned(
+                        first,
+                        include_indices.iter().map(|&i| store.get(i).unwrap()),
+                    ),
+                    None => {
+                        solver.eval_union(include_indices.iter().map(|&i| store.get(i).unwrap()))
+                    }
+                }
             }
-            Node::Intersection(kids) => {
+            NodeRef::Intersection(kids) => {
                 // A&B&C'&D' == (A&B)-(C|D)
                 include_indices.clear();
                 exclude_indices.clear();
                 for k in kids {
                     if k.is_neg() {
-                        if cache_vec[k.raw() as usize].is_some() {
+                        if store.get(k.raw() as usize).is_some() {
                             // & is faster, so if the negative is computed, include it
                             include_indices.push(k.raw() as usize);
                         } else {
@@ -450,42 +1094,65 @@ impl<T> Expression<T> {
                     }
                 }
 
+                // smallest first, only when the solver gives estimates
+                if include_indices.len() > 1
+                    && include_indices
+                        .iter()
+                        .any(|&i| solver.estimate_size(store.get(i).unwrap()) != 0)
+                {
+                    include_indices.sort_by_key(|&i| solver.estimate_size(store.get(i).unwrap()));
+                }
+
                 // intersections must have at least two terms
                 if exclude_indices.is_empty() {
                     // no exclusions so use the include as the result
-                    let include = solver.eval_intersection(
-                        include_indices
-                            .iter()
-                            .map(|&i| cache_vec[i].as_ref().unwrap()),
-                    )?;
-                    Ok(include)
+                    Self::intersect(solver, store, include_indices, &reusable)
                 } else {
+                    // pure exclusions, A'&B' == (A|B)', so try a native NOR first
+                    if include_indices.is_empty()
+                        && let Some(nor) = solver
+                            .eval_nor(exclude_indices.iter().map(|&i| store.get(i).unwrap()))?
+                    {
+                        return Ok(nor);
+                    }
+
+                    // get exclude (must be more than 1)
+                    let owned_exclude =
+                        if exclude_indices.len() == 1 {
+                            None
+                        } else {
+                            Some(solver.eval_union(
+                                exclude_indices.iter().map(|&i| store.get(i).unwrap()),
+                            )?)
+                        };
+
+                    if include_indices.is_empty() {
+                        // pure exclusions, A'&B' == (A|B)', so try to skip the universe
+                        let exclude = match &owned_exclude {
+                            Some(exclude) => exclude,
+                            None => store.get(exclude_indices[0]).unwrap(),
+                        };
+                        if let Some(complement) = solver.eval_complement(exclude)? {
+                            return Ok(complement);
+                        }
+                        // use universe if no inclusions are present
+                        Self::load_universal(solver, store)?;
+                    }
+
                     // get include
+                    let owned_include;
                     let include = if include_indices.is_empty() {
-                        // use universe if no inclusions are present
-                        if cache_vec[1].is_none() {
-                            cache_vec[1] = Some(solver.get_universal()?);
-                        }
-                        cache_vec[1].as_ref().unwrap()
+                        store.get(1).unwrap()
                     } else if include_indices.len() == 1 {
-                        cache_vec[include_indices[0]].as_ref().unwrap()
+                        store.get(include_indices[0]).unwrap()
                     } else {
-                        &solver.eval_intersection(
-                            include_indices
-                                .iter()
-                                .map(|&i| cache_vec[i].as_ref().unwrap()),
-                        )?
+                        owned_include = Self::intersect(solver, store, include_indices, &reusable)?;
+                        &owned_include
                     };
 
-                    // get exclude (must be more than 1)
-                    let exclude = if exclude_indices.len() == 1 {
-                        cache_vec[exclude_indices[0]].as_ref().unwrap()
-                    } else {
-                        &solver.eval_union(
-                            exclude_indices
-                                .iter()
-                                .map(|&i| cache_vec[i].as_ref().unwrap()),
-                        )?
+                    let exclude = match &owned_exclude {
+                        Some(exclude) => exclude,
+                        None => store.get(exclude_indices[0]).unwrap(),
                     };
 
                     // compute difference
@@ -0,0 +1,193 @@
+use crate::eval::Evaluator;
+use fixedbitset::FixedBitSet;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// A solver specialized for packed bitsets, with one bit per entity.
+///
+/// Every result is a [`FixedBitSet`] of a fixed universe length. Unions, intersections, and
+/// differences run word-by-word in place, and negations flip the bits directly, so the
+/// Universal set is never materialized.
+///
+/// Requires the `bitset` feature.
+///
+/// # Logic Semantics
+/// * **Variables:** Consumed during evaluation, like [`BitwiseEval`](crate::eval::BitwiseEval).
+///   See [`keep_variables`](Self::keep_variables) to clone them instead.
+/// * **Missing Terms:** Resolve to an all-zero bitset.
+///
+/// # Example
+/// ```rust
+/// use fixedbitset::FixedBitSet;
+/// use logify::{ExpressionBuilder, eval::BitSetEval};
+///
+/// let builder = ExpressionBuilder::new();
+/// builder.add_root(builder.leaf("Active") & !builder.leaf("Banned"));
+/// let expr = builder.build();
+///
+/// let mut solver = BitSetEval::new(8);
+/// solver.insert("Active", FixedBitSet::with_capacity_and_blocks(8, [0b0111_1110]));
+/// solver.insert("Banned", FixedBitSet::with_capacity_and_blocks(8, [0b0000_0110]));
+///
+/// let result = expr.evaluate(&mut solver).unwrap();
+/// assert_eq!(result[0].ones().collect::<Vec<_>>(), vec![3, 4, 5, 6]);
+/// ```
+#[derive(Clone)]
+pub struct BitSetEval<K> {
+    pub variables: HashMap<K, FixedBitSet>,
+    pub keep_variables: bool,
+    len: usize,
+}
+
+impl<K> BitSetEval<K> {
+    /// Creates a new solver over a universe of `len` entities.
+    pub fn new(len: usize) -> Self {
+        Self {
+            variables: HashMap::new(),
+            keep_variables: false,
+            len,
+        }
+    }
+
+    /// Clones variables during evaluation instead of consuming them.
+    ///
+    /// A consumed variable is gone after its first use, so a term that is evaluated again
+    /// (for example, when separate roots share it, or across evaluations) would otherwise
+    /// resolve as missing. Every use now costs a clone of the bitset.
+    ///
+    /// # Example
+    /// ```rust
+    /// use fixedbitset::FixedBitSet;
+    /// use logify::{ExpressionBuilder, eval::BitSetEval};
+    ///
+    /// let builder = ExpressionBuilder::new();
+    /// builder.add_root(builder.leaf("A") & builder.leaf("B"));
+    /// let expr = builder.build();
+    ///
+    /// let mut solver = BitSetEval::new(8).keep_variables();
+    /// solver.insert("A", FixedBitSet::with_capacity_and_blocks(8, [0b0110]));
+    /// solver.insert("B", FixedBitSet::with_capacity_and_blocks(8, [0b0011]));
+    /// assert_eq!(expr.evaluate(&mut solver).unwrap()[0].ones().collect::<Vec<_>>(), [1]);
+    /// // still registered, so a second run sees the same data
+    /// assert_eq!(expr.evaluate(&mut solver).unwrap()[0].ones().collect::<Vec<_>>(), [1]);
+    /// ```
+    pub fn keep_variables(mut self) -> Self {
+        self.keep_variables = true;
+        self
+    }
+
+    /// Returns the number of entities in the universe.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the universe has no entities.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Registers a variable for the next evaluation.
+    ///
+    /// Shorter bitsets are padded with zeros to the universe length.
+    ///
+    /// *Note: The value is moved into the solver and will be consumed (removed)
+    /// when the matching leaf node is evaluated, unless
+    /// [`keep_variables`](Self::keep_variables) is set.*
+    pub fn insert(&mut self, key: K, mut value: FixedBitSet)
+    where
+        K: Hash + Eq,
+    {
+        value.grow(self.len);
+        self.variables.insert(key, value);
+    }
+}
+
+impl<K: Hash + Eq> Evaluator<K, FixedBitSet, ()> for BitSetEval<K> {
+    fn get_universal(&mut self) -> Result<FixedBitSet, ()> {
+        let mut universal = FixedBitSet::with_capacity(self.len);
+        universal.insert_range(..);
+        Ok(universal)
+    }
+
+    fn get_empty(&mut self) -> Result<FixedBitSet, ()> {
+        Ok(FixedBitSet::with_capacity(self.len))
+    }
+
+    fn eval_set(&mut self, key: &K) -> Result<FixedBitSet, ()> {
+        let value = if self.keep_variables {
+            self.variables.get(key).cloned()
+        } else {
+            self.variables.remove(key)
+        };
+        match value {
+            Some(value) => Ok(value),
+            None => self.get_empty(),
+        }
+    }
+
+    fn eval_union<'a, I>(&mut self, values: I) -> Result<FixedBitSet, ()>
+    where
+        I: IntoIterator<Item = &'a FixedBitSet>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let mut iter = values.into_iter();
+        let first = iter.next().unwrap().clone();
+        self.eval_union_owned(first, iter)
+    }
+
+    fn eval_union_owned<'a, I>(
+        &mut self,
+        mut first: FixedBitSet,
+        rest: I,
+    ) -> Result<FixedBitSet, ()>
+    where
+        I: IntoIterator<Item = &'a FixedBitSet>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        for item in rest {
+            first.union_with(item);
+        }
+        Ok(first)
+    }
+
+    fn eval_intersection<'a, I>(&mut self, values: I) -> Result<FixedBitSet, ()>
+    where
+        I: IntoIterator<Item = &'a FixedBitSet>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let mut iter = values.into_iter();
+        let first = iter.next().unwrap().clone();
+        self.eval_intersection_owned(first, iter)
+    }
+
+    fn eval_intersection_owned<'a, I>(
+        &mut self,
+        mut first: FixedBitSet,
+        rest: I,
+    ) -> Result<FixedBitSet, ()>
+    where
+        I: IntoIterator<Item = &'a FixedBitSet>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        for item in rest {
+            first.intersect_with(item);
+        }
+        Ok(first)
+    }
+
+    fn eval_difference(
+        &mut self,
+        include: &FixedBitSet,
+        exclude: &FixedBitSet,
+    ) -> Result<FixedBitSet, ()> {
+        let mut result = include.clone();
+        result.difference_with(exclude);
+        Ok(result)
+    }
+
+    fn eval_complement(&mut self, value: &FixedBitSet) -> Result<Option<FixedBitSet>, ()> {
+        let mut result = value.clone();
+        result.toggle_range(..);
+        Ok(Some(result))
+    }
+}
@@ -10,7 +10,7 @@ use std::ops::{BitAndAssign, BitOrAssign, Sub};
 ///
 /// # Logic Semantics
 /// * **Variables:** Treated as transient input. They are **removed** from the solver during evaluation
-///   to avoid unnecessary cloning.
+///   to avoid unnecessary cloning. See [`keep_variables`](Self::keep_variables) to clone them instead.
 /// * **Universal Set:** Treated as persistent context. It is **cloned** (not consumed), so large
 ///   structures should be wrapped in `Arc` or `Rc`.
 /// * **Operations:** Uses in-place mutation (`|=`, `&=`) to minimize memory allocation overhead
@@ -41,6 +41,20 @@ use std::ops::{BitAndAssign, BitOrAssign, Sub};
 pub struct BitwiseEval<K, S> {
     pub variables: HashMap<K, S>,
     pub universal: S,
+    pub missing_as_universal: bool,
+    pub keep_variables: bool,
+    pub defaults: HashMap<K, DefaultPolicy<S>>,
+}
+
+/// What a [`BitwiseEval`] term resolves to when no variable is registered for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DefaultPolicy<S> {
+    /// Matches nothing.
+    Empty,
+    /// Matches everything, so the term adds no constraint.
+    Universal,
+    /// A fixed fallback value, cloned on each use.
+    Value(S),
 }
 
 impl<K, S> BitwiseEval<K, S> {
@@ -52,13 +66,105 @@ impl<K, S> BitwiseEval<K, S> {
         Self {
             variables: HashMap::new(),
             universal,
+            missing_as_universal: false,
+            keep_variables: false,
+            defaults: HashMap::new(),
         }
     }
 
+    /// Resolves unregistered variables to the Universal set instead of the Empty set.
+    ///
+    /// By default a missing variable matches nothing. In this mode it means "no constraint",
+    /// which suits optional filter dimensions.
+    ///
+    /// *Note: Variables are consumed during evaluation (unless
+    /// [`keep_variables`](Self::keep_variables) is set), so any variable not re-inserted before
+    /// the next evaluation will also resolve to the Universal set.*
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{ExpressionBuilder, eval::BitwiseEval};
+    ///
+    /// let builder = ExpressionBuilder::new();
+    /// builder.add_root(builder.leaf("Color") & builder.leaf("Size"));
+    /// let expr = builder.build();
+    ///
+    /// // No "Size" filter was given, so it doesn't constrain the result
+    /// let mut solver = BitwiseEval::new(0b1111u8).missing_as_universal();
+    /// solver.insert("Color", 0b0110);
+    /// assert_eq!(expr.evaluate(&mut solver), Ok(vec![0b0110]));
+    /// ```
+    pub fn missing_as_universal(mut self) -> Self {
+        self.missing_as_universal = true;
+        self
+    }
+
+    /// Clones variables during evaluation instead of consuming them.
+    ///
+    /// A consumed variable is gone after its first use, so a term that is evaluated again
+    /// (for example, when separate roots share it, or across evaluations) would otherwise
+    /// resolve as missing. Every use now costs a clone, so large sets should be wrapped in
+    /// `Arc` or `Rc`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{ExpressionBuilder, eval::BitwiseEval};
+    ///
+    /// let builder = ExpressionBuilder::new();
+    /// builder.add_root(builder.leaf("A") & builder.leaf("B"));
+    /// let expr = builder.build();
+    ///
+    /// let mut solver = BitwiseEval::new(0b1111u8).keep_variables();
+    /// solver.insert("A", 0b0110);
+    /// solver.insert("B", 0b0011);
+    /// assert_eq!(expr.evaluate(&mut solver), Ok(vec![0b0010]));
+    /// // still registered, so a second run sees the same data
+    /// assert_eq!(expr.evaluate(&mut solver), Ok(vec![0b0010]));
+    /// ```
+    pub fn keep_variables(mut self) -> Self {
+        self.keep_variables = true;
+        self
+    }
+
+    /// Sets per-term fallbacks for terms without a registered variable.
+    ///
+    /// A term's policy takes priority over [`missing_as_universal`](Self::missing_as_universal),
+    /// which still applies to terms without a policy. Unlike variables, defaults persist
+    /// across evaluations.
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::collections::HashMap;
+    /// use logify::{ExpressionBuilder, eval::{BitwiseEval, DefaultPolicy}};
+    ///
+    /// let builder = ExpressionBuilder::new();
+    /// builder.add_root(builder.leaf("Color") & builder.leaf("Stock"));
+    /// let expr = builder.build();
+    ///
+    /// // "Color" is an optional dimension, "Stock" is required data
+    /// let defaults = HashMap::from([
+    ///     ("Color", DefaultPolicy::Universal),
+    ///     ("Stock", DefaultPolicy::Empty),
+    /// ]);
+    ///
+    /// let mut solver = BitwiseEval::new(0b1111u8).with_defaults(defaults.clone());
+    /// solver.insert("Stock", 0b0011);
+    /// assert_eq!(expr.evaluate(&mut solver), Ok(vec![0b0011]));
+    ///
+    /// let mut solver = BitwiseEval::new(0b1111u8).with_defaults(defaults);
+    /// solver.insert("Color", 0b0110);
+    /// assert_eq!(expr.evaluate(&mut solver), Ok(vec![0b0000]));
+    /// ```
+    pub fn with_defaults(mut self, defaults: HashMap<K, DefaultPolicy<S>>) -> Self {
+        self.defaults = defaults;
+        self
+    }
+
     /// Registers a variable for the next evaluation.
     ///
     /// *Note: The value is moved into the solver and will be consumed (removed)
-    /// when the matching leaf node is evaluated.*
+    /// when the matching leaf node is evaluated, unless
+    /// [`keep_variables`](Self::keep_variables) is set.*
     pub fn insert(&mut self, key: K, value: S)
     where
         K: Hash + Eq,
@@ -83,7 +189,21 @@ where
     }
 
     fn eval_set(&mut self, key: &K) -> Result<S, ()> {
-        Ok(self.variables.remove(key).unwrap_or_default())
+        let value = if self.keep_variables {
+            self.variables.get(key).cloned()
+        } else {
+            self.variables.remove(key)
+        };
+        if let Some(value) = value {
+            return Ok(value);
+        }
+        match self.defaults.get(key) {
+            Some(DefaultPolicy::Empty) => Ok(S::default()),
+            Some(DefaultPolicy::Universal) => Ok(self.universal.clone()),
+            Some(DefaultPolicy::Value(value)) => Ok(value.clone()),
+            None if self.missing_as_universal => Ok(self.universal.clone()),
+            None => Ok(S::default()),
+        }
     }
 
     fn eval_union<'a, I>(&mut self, values: I) -> Result<S, ()>
@@ -93,11 +213,20 @@ where
         I::IntoIter: ExactSizeIterator,
     {
         let mut iter = values.into_iter();
-        let mut result = iter.next().unwrap().clone();
-        for item in iter {
-            result |= item;
+        let first = iter.next().unwrap().clone();
+        self.eval_union_owned(first, iter)
+    }
+
+    fn eval_union_owned<'a, I>(&mut self, mut first: S, rest: I) -> Result<S, ()>
+    where
+        S: 'a,
+        I: IntoIterator<Item = &'a S>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        for item in rest {
+            first |= item;
         }
-        Ok(result)
+        Ok(first)
     }
 
     fn eval_intersection<'a, I>(&mut self, values: I) -> Result<S, ()>
@@ -107,11 +236,20 @@ where
         I::IntoIter: ExactSizeIterator,
     {
         let mut iter = values.into_iter();
-        let mut result = iter.next().unwrap().clone();
-        for item in iter {
-            result &= item;
+        let first = iter.next().unwrap().clone();
+        self.eval_intersection_owned(first, iter)
+    }
+
+    fn eval_intersection_owned<'a, I>(&mut self, mut first: S, rest: I) -> Result<S, ()>
+    where
+        S: 'a,
+        I: IntoIterator<Item = &'a S>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        for item in rest {
+            first &= item;
         }
-        Ok(result)
+        Ok(first)
     }
 
     fn eval_difference(&mut self, include: &S, exclude: &S) -> Result<S, ()> {
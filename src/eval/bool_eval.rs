@@ -1,4 +1,5 @@
-use crate::eval::Evaluator;
+use crate::eval::{Evaluator, EvaluatorCache};
+use crate::expr::{Expression, Node};
 use std::collections::HashSet;
 use std::hash::Hash;
 
@@ -11,6 +12,10 @@ use std::hash::Hash;
 ///   as soon as the result is known (e.g., `false & ...` stops immediately).
 /// * **Lightweight:** No complex cloning or set allocations.
 ///
+/// Keys that were never added are treated as false. To tell "false" apart from "not yet
+/// known", use [`KleeneEval`](crate::eval::KleeneEval), which evaluates missing terms as
+/// [`Kleene::Unknown`](crate::eval::Kleene::Unknown).
+///
 /// # Example
 /// ```rust
 /// use logify::eval::BoolEval;
@@ -46,6 +51,57 @@ impl<T: Hash + Eq> BoolEval<T> {
     pub fn add(&mut self, key: T) {
         self.active_keys.insert(key);
     }
+
+    /// Marks a key as "Present" and re-evaluates, recomputing only what the key can affect.
+    ///
+    /// Cached results for the key's leaf and every node above it are dropped, and everything
+    /// else is reused from `cache`. If the key was already present, the cached results are
+    /// returned as-is. This suits permission checks where keys are only ever added between
+    /// evaluations. The cache must only be used with this evaluator, since it is trusted to
+    /// hold results for the keys added so far.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{EvaluatorCache, ExpressionBuilder, eval::BoolEval};
+    ///
+    /// let builder = ExpressionBuilder::new();
+    /// builder.add_root(builder.leaf("Admin") | builder.leaf("Owner"));
+    /// builder.add_root(builder.leaf("Editor") & !builder.leaf("Banned"));
+    /// let expr = builder.build();
+    ///
+    /// let mut ctx = BoolEval::new();
+    /// let mut cache = EvaluatorCache::new();
+    /// assert_eq!(ctx.add_and_evaluate("Editor", &expr, &mut cache), Ok(vec![false, true]));
+    /// assert_eq!(ctx.add_and_evaluate("Owner", &expr, &mut cache), Ok(vec![true, true]));
+    /// assert_eq!(ctx.add_and_evaluate("Banned", &expr, &mut cache), Ok(vec![true, false]));
+    /// ```
+    #[allow(clippy::result_unit_err)] // the error type of the `Evaluator` impl
+    pub fn add_and_evaluate<Meta>(
+        &mut self,
+        key: T,
+        expr: &Expression<T, Meta>,
+        cache: &mut EvaluatorCache<bool>,
+    ) -> Result<Vec<bool>, ()> {
+        if !self.active_keys.contains(&key) && cache.expr_uuid == expr.uuid {
+            // children come before parents, so one forward pass finds every dependent
+            let mut dirty = vec![false; expr.nodes.len()];
+            for (idx, node) in expr.nodes.iter().enumerate() {
+                dirty[idx] = match node {
+                    Node::Empty => false,
+                    Node::Set(value) => *value == key,
+                    Node::Union(kids) | Node::Intersection(kids) => {
+                        kids.iter().any(|k| dirty[k.idx()])
+                    }
+                };
+                if dirty[idx] {
+                    cache.cache.get_mut(idx << 1).map(Option::take);
+                    cache.cache.get_mut((idx << 1) | 1).map(Option::take);
+                }
+            }
+        }
+        self.active_keys.insert(key);
+        expr.evaluate_with(self, cache)
+    }
 }
 
 impl<T: Hash + Eq> Evaluator<T, bool, ()> for BoolEval<T> {
@@ -79,4 +135,16 @@ impl<T: Hash + Eq> Evaluator<T, bool, ()> for BoolEval<T> {
     fn eval_difference(&mut self, include: &bool, exclude: &bool) -> Result<bool, ()> {
         Ok(*include && !*exclude)
     }
+
+    fn eval_complement(&mut self, value: &bool) -> Result<Option<bool>, ()> {
+        Ok(Some(!*value))
+    }
+
+    fn eval_nor<'a, I>(&mut self, excludes: I) -> Result<Option<bool>, ()>
+    where
+        I: IntoIterator<Item = &'a bool>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        Ok(Some(!excludes.into_iter().any(|&v| v)))
+    }
 }
@@ -0,0 +1,181 @@
+use std::fmt::{self, Display};
+
+use crate::eval::Evaluator;
+
+/// The error returned by a [`Bounded`] evaluator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BoundedError<E> {
+    /// The wrapped evaluator failed.
+    Inner(E),
+    /// An intermediate result grew past the configured cap.
+    ResultTooLarge {
+        /// The size of the offending result.
+        size: u64,
+        /// The configured maximum.
+        max: u64,
+    },
+}
+
+impl<E: Display> Display for BoundedError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoundedError::Inner(e) => write!(f, "{}", e),
+            BoundedError::ResultTooLarge { size, max } => {
+                write!(f, "result of size {} exceeds the maximum of {}", size, max)
+            }
+        }
+    }
+}
+
+impl<E: fmt::Debug + Display> std::error::Error for BoundedError<E> {}
+
+/// A decorator that fails fast when any intermediate result grows too large.
+///
+/// Wraps another [`Evaluator`] and measures every result it produces with a user-supplied
+/// size function (e.g. the length of a set). If a set, union, intersection, or difference exceeds
+/// `max`, evaluation stops with [`BoundedError::ResultTooLarge`] instead of continuing to
+/// allocate. The Universal set itself is not measured, only results derived from it.
+///
+/// # Example
+/// ```rust
+/// use logify::{ExpressionBuilder, eval::{BitwiseEval, Bounded, BoundedError}};
+///
+/// let builder = ExpressionBuilder::new();
+/// builder.add_root(!builder.leaf("Rare"));
+/// let expr = builder.build();
+///
+/// // Sets are bitmasks over 64 items
+/// let mut inner = BitwiseEval::new(u64::MAX);
+/// inner.insert("Rare", 0b11);
+///
+/// // "Everything except Rare" is far larger than allowed
+/// let mut solver = Bounded::new(inner, 16, |r: &u64| r.count_ones() as u64);
+/// let result = expr.evaluate(&mut solver);
+/// assert_eq!(result, Err(BoundedError::ResultTooLarge { size: 62, max: 16 }));
+/// ```
+#[derive(Clone)]
+pub struct Bounded<S, F> {
+    pub inner: S,
+    pub max: u64,
+    size_of: F,
+}
+
+impl<S, F> Bounded<S, F> {
+    /// Wraps `inner`, failing once a result measured by `size_of` exceeds `max`.
+    pub fn new(inner: S, max: u64, size_of: F) -> Self {
+        Self {
+            inner,
+            max,
+            size_of,
+        }
+    }
+
+    /// Unwraps the decorator, returning the inner evaluator.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn check<R, E>(&mut self, result: Result<R, E>) -> Result<R, BoundedError<E>>
+    where
+        F: FnMut(&R) -> u64,
+    {
+        let result = result.map_err(BoundedError::Inner)?;
+        let size = (self.size_of)(&result);
+        if size > self.max {
+            return Err(BoundedError::ResultTooLarge {
+                size,
+                max: self.max,
+            });
+        }
+        Ok(result)
+    }
+}
+
+impl<T, R, E, S, F> Evaluator<T, R, BoundedError<E>> for Bounded<S, F>
+where
+    S: Evaluator<T, R, E>,
+    F: FnMut(&R) -> u64,
+{
+    fn get_universal(&mut self) -> Result<R, BoundedError<E>> {
+        self.inner.get_universal().map_err(BoundedError::Inner)
+    }
+
+    fn get_empty(&mut self) -> Result<R, BoundedError<E>> {
+        self.inner.get_empty().map_err(BoundedError::Inner)
+    }
+
+    fn eval_set(&mut self, set: &T) -> Result<R, BoundedError<E>> {
+        let result = self.inner.eval_set(set);
+        self.check(result)
+    }
+
+    fn eval_union<'a, I>(&mut self, values: I) -> Result<R, BoundedError<E>>
+    where
+        R: 'a,
+        I: IntoIterator<Item = &'a R>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let result = self.inner.eval_union(values);
+        self.check(result)
+    }
+
+    fn eval_union_owned<'a, I>(&mut self, first: R, rest: I) -> Result<R, BoundedError<E>>
+    where
+        R: 'a,
+        I: IntoIterator<Item = &'a R>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let result = self.inner.eval_union_owned(first, rest);
+        self.check(result)
+    }
+
+    fn eval_intersection<'a, I>(&mut self, values: I) -> Result<R, BoundedError<E>>
+    where
+        R: 'a,
+        I: IntoIterator<Item = &'a R>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let result = self.inner.eval_intersection(values);
+        self.check(result)
+    }
+
+    fn eval_intersection_owned<'a, I>(&mut self, first: R, rest: I) -> Result<R, BoundedError<E>>
+    where
+        R: 'a,
+        I: IntoIterator<Item = &'a R>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let result = self.inner.eval_intersection_owned(first, rest);
+        self.check(result)
+    }
+
+    fn eval_difference(&mut self, include: &R, exclude: &R) -> Result<R, BoundedError<E>> {
+        let result = self.inner.eval_difference(include, exclude);
+        self.check(result)
+    }
+
+    fn eval_complement(&mut self, value: &R) -> Result<Option<R>, BoundedError<E>> {
+        match self.inner.eval_complement(value) {
+            Ok(Some(result)) => self.check(Ok(result)).map(Some),
+            Ok(None) => Ok(None),
+            Err(e) => Err(BoundedError::Inner(e)),
+        }
+    }
+
+    fn eval_nor<'a, I>(&mut self, excludes: I) -> Result<Option<R>, BoundedError<E>>
+    where
+        R: 'a,
+        I: IntoIterator<Item = &'a R>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        match self.inner.eval_nor(excludes) {
+            Ok(Some(result)) => self.check(Ok(result)).map(Some),
+            Ok(None) => Ok(None),
+            Err(e) => Err(BoundedError::Inner(e)),
+        }
+    }
+
+    fn estimate_size(&self, value: &R) -> usize {
+        self.inner.estimate_size(value)
+    }
+}
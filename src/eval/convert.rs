@@ -0,0 +1,73 @@
+#[cfg(feature = "json")]
+mod impl_json {
+    use std::io::{Read, Write};
+
+    use serde::{Deserialize, Serialize};
+
+    use crate::eval::EvaluatorCache;
+
+    impl<R: Serialize> EvaluatorCache<R> {
+        /// Writes the cache as JSON, allowing a restarted process to warm start.
+        ///
+        /// The cache remembers the UUID of the expression it was built for, so it should
+        /// be saved alongside that expression. Loading it against any other expression is
+        /// safe, the cache will simply be cleared on the next evaluation.
+        ///
+        /// # Example
+        /// ```rust
+        /// use logify::{EvaluatorCache, Expression, ExpressionBuilder, eval::BoolEval};
+        ///
+        /// let builder = ExpressionBuilder::new();
+        /// let a = builder.leaf("A");
+        /// let b = builder.leaf("B");
+        /// builder.add_root(a & !b);
+        /// let expr: Expression<&str> = builder.build();
+        ///
+        /// let mut solver = BoolEval::new();
+        /// solver.add("A");
+        ///
+        /// // populate and save the cache
+        /// let mut cache = EvaluatorCache::new();
+        /// let cold = expr.evaluate_with(&mut solver, &mut cache).unwrap();
+        /// let mut bytes = Vec::new();
+        /// cache.save_to(&mut bytes).unwrap();
+        ///
+        /// // reload, the cached results are reused without calling the solver
+        /// let mut cache: EvaluatorCache<bool> = EvaluatorCache::load_from(&bytes[..]).unwrap();
+        /// let mut empty_solver = BoolEval::new();
+        /// let warm = expr.evaluate_with(&mut empty_solver, &mut cache).unwrap();
+        /// assert_eq!(cold, warm);
+        /// ```
+        pub fn save_to<W: Write>(&self, writer: W) -> Result<(), serde_json::Error> {
+            serde_json::to_writer(writer, self)
+        }
+    }
+
+    impl<R: for<'a> Deserialize<'a>> EvaluatorCache<R> {
+        /// Reads a cache previously written with [`save_to`](Self::save_to).
+        pub fn load_from<Rd: Read>(reader: Rd) -> Result<Self, serde_json::Error> {
+            serde_json::from_reader(reader)
+        }
+    }
+}
+
+#[cfg(feature = "fast-binary")]
+mod impl_fast_binary {
+    use bitcode::{Decode, Encode};
+
+    use crate::eval::EvaluatorCache;
+
+    impl<R: Encode> EvaluatorCache<R> {
+        /// Encodes the cache into a compact binary form for warm starts.
+        pub fn to_bitcode_bytes(&self) -> Vec<u8> {
+            bitcode::encode(self)
+        }
+    }
+
+    impl<R: for<'a> Decode<'a>> EvaluatorCache<R> {
+        /// Decodes a cache previously encoded with [`to_bitcode_bytes`](Self::to_bitcode_bytes).
+        pub fn from_bitcode_bytes(bytes: &[u8]) -> Result<Self, bitcode::Error> {
+            bitcode::decode(bytes)
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use crate::eval::Evaluator;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// An evaluator for fuzzy logic.
+///
+/// Each term has a membership degree in `[0, 1]` instead of a plain true/false.
+///
+/// # Logic Semantics
+/// * **Union:** The maximum of the children.
+/// * **Intersection:** The minimum of the children.
+/// * **Difference:** `min(include, 1 - exclude)`, using the standard fuzzy complement.
+/// * **Missing Terms:** Have a degree of `0.0`.
+///
+/// # Example
+/// ```rust
+/// use logify::{ExpressionBuilder, eval::FuzzyEval};
+///
+/// let builder = ExpressionBuilder::new();
+/// builder.add_root(builder.leaf("Warm") & !builder.leaf("Humid"));
+/// let expr = builder.build();
+///
+/// let mut ctx = FuzzyEval::new();
+/// ctx.insert("Warm", 0.8);
+/// ctx.insert("Humid", 0.3);
+///
+/// // min(0.8, 1 - 0.3)
+/// assert_eq!(expr.evaluate(&mut ctx), Ok(vec![0.7]));
+/// ```
+#[derive(Clone)]
+pub struct FuzzyEval<K: Hash + Eq> {
+    degrees: HashMap<K, f64>,
+}
+
+impl<K: Hash + Eq> Default for FuzzyEval<K> {
+    fn default() -> Self {
+        Self {
+            degrees: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Hash + Eq> FuzzyEval<K> {
+    /// New blank `FuzzyEval`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the membership degree of a term, clamped to `[0, 1]`.
+    pub fn insert(&mut self, key: K, degree: f64) {
+        self.degrees.insert(key, degree.clamp(0.0, 1.0));
+    }
+}
+
+impl<K: Hash + Eq> Evaluator<K, f64, ()> for FuzzyEval<K> {
+    fn get_universal(&mut self) -> Result<f64, ()> {
+        Ok(1.0)
+    }
+    fn get_empty(&mut self) -> Result<f64, ()> {
+        Ok(0.0)
+    }
+
+    fn eval_set(&mut self, set: &K) -> Result<f64, ()> {
+        Ok(self.degrees.get(set).copied().unwrap_or(0.0))
+    }
+
+    fn eval_union<'a, I>(&mut self, values: I) -> Result<f64, ()>
+    where
+        I: IntoIterator<Item = &'a f64>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        Ok(values.into_iter().fold(0.0, |acc, &v| acc.max(v)))
+    }
+
+    fn eval_intersection<'a, I>(&mut self, values: I) -> Result<f64, ()>
+    where
+        I: IntoIterator<Item = &'a f64>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        Ok(values.into_iter().fold(1.0, |acc, &v| acc.min(v)))
+    }
+
+    fn eval_difference(&mut self, include: &f64, exclude: &f64) -> Result<f64, ()> {
+        Ok(include.min(1.0 - exclude))
+    }
+
+    fn eval_complement(&mut self, value: &f64) -> Result<Option<f64>, ()> {
+        Ok(Some(1.0 - value))
+    }
+}
@@ -0,0 +1,123 @@
+use crate::eval::Evaluator;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Not;
+
+/// A truth value in Kleene's three-valued logic.
+///
+/// The variants are ordered `False < Unknown < True`, so OR is the maximum and AND the minimum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Kleene {
+    /// Definitely false.
+    False,
+    /// Could be either, like SQL's `NULL`.
+    Unknown,
+    /// Definitely true.
+    True,
+}
+
+/// Kleene negation: swaps True and False, and keeps Unknown.
+impl Not for Kleene {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        match self {
+            Kleene::False => Kleene::True,
+            Kleene::Unknown => Kleene::Unknown,
+            Kleene::True => Kleene::False,
+        }
+    }
+}
+
+impl From<bool> for Kleene {
+    fn from(value: bool) -> Self {
+        if value { Kleene::True } else { Kleene::False }
+    }
+}
+
+/// An evaluator for Kleene three-valued logic.
+///
+/// Terms are True, False, or Unknown, matching SQL `NULL` semantics for data with missing fields.
+///
+/// # Logic Semantics
+/// * **Union:** True if any child is True, else Unknown if any child is Unknown.
+/// * **Intersection:** False if any child is False, else Unknown if any child is Unknown.
+/// * **Difference:** `include AND NOT exclude`, where `NOT Unknown` is Unknown.
+/// * **Missing Terms:** Are Unknown.
+///
+/// # Example
+/// ```rust
+/// use logify::{ExpressionBuilder, eval::{Kleene, KleeneEval}};
+///
+/// let builder = ExpressionBuilder::new();
+/// builder.add_root(builder.leaf("InStock") & !builder.leaf("Discontinued"));
+/// builder.add_root(builder.leaf("InStock") | builder.leaf("Preorder"));
+/// let expr = builder.build();
+///
+/// // "Discontinued" and "Preorder" are missing from this row
+/// let mut ctx = KleeneEval::new();
+/// ctx.insert("InStock", Kleene::True);
+///
+/// assert_eq!(expr.evaluate(&mut ctx), Ok(vec![Kleene::Unknown, Kleene::True]));
+/// ```
+#[derive(Clone)]
+pub struct KleeneEval<K: Hash + Eq> {
+    values: HashMap<K, Kleene>,
+}
+
+impl<K: Hash + Eq> Default for KleeneEval<K> {
+    fn default() -> Self {
+        Self {
+            values: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Hash + Eq> KleeneEval<K> {
+    /// New blank `KleeneEval`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the truth value of a term.
+    pub fn insert(&mut self, key: K, value: Kleene) {
+        self.values.insert(key, value);
+    }
+}
+
+impl<K: Hash + Eq> Evaluator<K, Kleene, ()> for KleeneEval<K> {
+    fn get_universal(&mut self) -> Result<Kleene, ()> {
+        Ok(Kleene::True)
+    }
+    fn get_empty(&mut self) -> Result<Kleene, ()> {
+        Ok(Kleene::False)
+    }
+
+    fn eval_set(&mut self, set: &K) -> Result<Kleene, ()> {
+        Ok(self.values.get(set).copied().unwrap_or(Kleene::Unknown))
+    }
+
+    fn eval_union<'a, I>(&mut self, values: I) -> Result<Kleene, ()>
+    where
+        I: IntoIterator<Item = &'a Kleene>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        Ok(values.into_iter().copied().max().unwrap_or(Kleene::False))
+    }
+
+    fn eval_intersection<'a, I>(&mut self, values: I) -> Result<Kleene, ()>
+    where
+        I: IntoIterator<Item = &'a Kleene>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        Ok(values.into_iter().copied().min().unwrap_or(Kleene::True))
+    }
+
+    fn eval_difference(&mut self, include: &Kleene, exclude: &Kleene) -> Result<Kleene, ()> {
+        Ok((*include).min(!*exclude))
+    }
+
+    fn eval_complement(&mut self, value: &Kleene) -> Result<Option<Kleene>, ()> {
+        Ok(Some(!*value))
+    }
+}
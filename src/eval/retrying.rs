@@ -0,0 +1,165 @@
+use crate::eval::{EvalError, Evaluator};
+
+/// A decorator that retries failed leaf lookups.
+///
+/// Wraps another [`Evaluator`] and re-runs `eval_set` when it fails with an error whose
+/// [`EvalError::is_retryable`] returns `true`. Before each retry, the `backoff` closure is
+/// called with the attempt number (starting at `1`), letting you sleep or log. Once
+/// `max_retries` is used up, the last error is returned. All other operations are passed
+/// through untouched.
+///
+/// # Example
+/// ```rust
+/// use logify::{ExpressionBuilder, eval::{EvalError, Evaluator, Retrying}};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Timeout;
+/// impl EvalError for Timeout {
+///     fn is_retryable(&self) -> bool { true }
+/// }
+///
+/// // Fails the first two lookups, like a flaky connection
+/// struct Flaky { failures: usize }
+/// impl Evaluator<&str, bool, Timeout> for Flaky {
+///     fn get_universal(&mut self) -> Result<bool, Timeout> { Ok(true) }
+///     fn get_empty(&mut self) -> Result<bool, Timeout> { Ok(false) }
+///     fn eval_set(&mut self, _: &&str) -> Result<bool, Timeout> {
+///         if self.failures > 0 {
+///             self.failures -= 1;
+///             return Err(Timeout);
+///         }
+///         Ok(true)
+///     }
+///     fn eval_union<'a, I>(&mut self, v: I) -> Result<bool, Timeout>
+///     where I: IntoIterator<Item = &'a bool>, I::IntoIter: ExactSizeIterator {
+///         Ok(v.into_iter().any(|&b| b))
+///     }
+///     fn eval_intersection<'a, I>(&mut self, v: I) -> Result<bool, Timeout>
+///     where I: IntoIterator<Item = &'a bool>, I::IntoIter: ExactSizeIterator {
+///         Ok(v.into_iter().all(|&b| b))
+///     }
+///     fn eval_difference(&mut self, a: &bool, b: &bool) -> Result<bool, Timeout> {
+///         Ok(*a && !*b)
+///     }
+/// }
+///
+/// let builder = ExpressionBuilder::new();
+/// builder.add_root(builder.leaf("A"));
+/// let expr = builder.build();
+///
+/// let mut attempts = Vec::new();
+/// let mut solver = Retrying::new(Flaky { failures: 2 }, 3, |n| attempts.push(n));
+/// assert_eq!(expr.evaluate(&mut solver), Ok(vec![true]));
+/// drop(solver);
+/// assert_eq!(attempts, vec![1, 2]);
+///
+/// let mut solver = Retrying::new(Flaky { failures: 2 }, 1, |_| {});
+/// assert_eq!(expr.evaluate(&mut solver), Err(Timeout));
+/// ```
+#[derive(Clone)]
+pub struct Retrying<S, B> {
+    pub inner: S,
+    pub max_retries: usize,
+    backoff: B,
+}
+
+impl<S, B> Retrying<S, B> {
+    /// Wraps `inner`, retrying a failed `eval_set` up to `max_retries` times.
+    pub fn new(inner: S, max_retries: usize, backoff: B) -> Self {
+        Self {
+            inner,
+            max_retries,
+            backoff,
+        }
+    }
+
+    /// Unwraps the decorator, returning the inner evaluator.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<T, R, E, S, B> Evaluator<T, R, E> for Retrying<S, B>
+where
+    S: Evaluator<T, R, E>,
+    E: EvalError,
+    B: FnMut(usize),
+{
+    fn get_universal(&mut self) -> Result<R, E> {
+        self.inner.get_universal()
+    }
+
+    fn get_empty(&mut self) -> Result<R, E> {
+        self.inner.get_empty()
+    }
+
+    fn eval_set(&mut self, set: &T) -> Result<R, E> {
+        let mut attempt = 0;
+        loop {
+            match self.inner.eval_set(set) {
+                Err(e) if e.is_retryable() && attempt < self.max_retries => {
+                    attempt += 1;
+                    (self.backoff)(attempt);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn eval_union<'a, I>(&mut self, values: I) -> Result<R, E>
+    where
+        R: 'a,
+        I: IntoIterator<Item = &'a R>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        self.inner.eval_union(values)
+    }
+
+    fn eval_union_owned<'a, I>(&mut self, first: R, rest: I) -> Result<R, E>
+    where
+        R: 'a,
+        I: IntoIterator<Item = &'a R>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        self.inner.eval_union_owned(first, rest)
+    }
+
+    fn eval_intersection<'a, I>(&mut self, values: I) -> Result<R, E>
+    where
+        R: 'a,
+        I: IntoIterator<Item = &'a R>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        self.inner.eval_intersection(values)
+    }
+
+    fn eval_intersection_owned<'a, I>(&mut self, first: R, rest: I) -> Result<R, E>
+    where
+        R: 'a,
+        I: IntoIterator<Item = &'a R>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        self.inner.eval_intersection_owned(first, rest)
+    }
+
+    fn eval_difference(&mut self, include: &R, exclude: &R) -> Result<R, E> {
+        self.inner.eval_difference(include, exclude)
+    }
+
+    fn eval_complement(&mut self, value: &R) -> Result<Option<R>, E> {
+        self.inner.eval_complement(value)
+    }
+
+    fn eval_nor<'a, I>(&mut self, excludes: I) -> Result<Option<R>, E>
+    where
+        R: 'a,
+        I: IntoIterator<Item = &'a R>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        self.inner.eval_nor(excludes)
+    }
+
+    fn estimate_size(&self, value: &R) -> usize {
+        self.inner.estimate_size(value)
+    }
+}
@@ -0,0 +1,195 @@
+use crate::eval::Evaluator;
+use roaring::RoaringBitmap;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::RangeBounds;
+
+/// A solver for compressed [`RoaringBitmap`]s of entity IDs.
+///
+/// Every result is a set of `u32` IDs. Unions and intersections run in place, differences
+/// use roaring's set subtraction, and negations subtract from the ID range given at
+/// construction, which is also returned as the Universal set.
+///
+/// Requires the `roaring` feature.
+///
+/// # Logic Semantics
+/// * **Variables:** Consumed during evaluation, like [`BitwiseEval`](crate::eval::BitwiseEval).
+///   See [`keep_variables`](Self::keep_variables) to clone them instead.
+/// * **Missing Terms:** Resolve to an empty bitmap.
+/// * **Ordering:** Intersections start from the smallest bitmap, see
+///   [`Evaluator::estimate_size`].
+///
+/// # Example
+/// ```rust
+/// use logify::{ExpressionBuilder, eval::RoaringEval};
+/// use roaring::RoaringBitmap;
+///
+/// let builder = ExpressionBuilder::new();
+/// builder.add_root(builder.leaf("Active") & !builder.leaf("Banned"));
+/// builder.add_root(!builder.leaf("Active"));
+/// let expr = builder.build();
+///
+/// let mut solver = RoaringEval::new(0..10);
+/// solver.insert("Active", RoaringBitmap::from_iter([1, 2, 3, 4]));
+/// solver.insert("Banned", RoaringBitmap::from_iter([2, 9]));
+///
+/// let result = expr.evaluate(&mut solver).unwrap();
+/// assert_eq!(result[0].iter().collect::<Vec<_>>(), [1, 3, 4]);
+/// assert_eq!(result[1].iter().collect::<Vec<_>>(), [0, 5, 6, 7, 8, 9]);
+/// ```
+#[derive(Clone)]
+pub struct RoaringEval<K> {
+    pub variables: HashMap<K, RoaringBitmap>,
+    pub keep_variables: bool,
+    universe: RoaringBitmap,
+}
+
+impl<K> RoaringEval<K> {
+    /// Creates a new solver whose Universal set is every ID in `range`.
+    pub fn new<B: RangeBounds<u32>>(range: B) -> Self {
+        let mut universe = RoaringBitmap::new();
+        universe.insert_range(range);
+        Self {
+            variables: HashMap::new(),
+            keep_variables: false,
+            universe,
+        }
+    }
+
+    /// Clones variables during evaluation instead of consuming them.
+    ///
+    /// A consumed variable is gone after its first use, so a term that is evaluated again
+    /// (for example, when separate roots share it, or across evaluations) would otherwise
+    /// resolve as missing. Every use now costs a clone of the bitmap.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{ExpressionBuilder, eval::RoaringEval};
+    /// use roaring::RoaringBitmap;
+    ///
+    /// let builder = ExpressionBuilder::new();
+    /// builder.add_root(builder.leaf("A") & builder.leaf("B"));
+    /// let expr = builder.build();
+    ///
+    /// let mut solver = RoaringEval::new(0..10).keep_variables();
+    /// solver.insert("A", RoaringBitmap::from_iter([1, 2]));
+    /// solver.insert("B", RoaringBitmap::from_iter([2, 3]));
+    /// assert_eq!(expr.evaluate(&mut solver).unwrap()[0].iter().collect::<Vec<_>>(), [2]);
+    /// // still registered, so a second run sees the same data
+    /// assert_eq!(expr.evaluate(&mut solver).unwrap()[0].iter().collect::<Vec<_>>(), [2]);
+    /// ```
+    pub fn keep_variables(mut self) -> Self {
+        self.keep_variables = true;
+        self
+    }
+
+    /// Returns the Universal set, every ID in the range given at construction.
+    pub fn universe(&self) -> &RoaringBitmap {
+        &self.universe
+    }
+
+    /// Registers a variable for the next evaluation.
+    ///
+    /// IDs outside the universe are kept, so they survive unions and intersections but are
+    /// dropped by negations.
+    ///
+    /// *Note: The value is moved into the solver and will be consumed (removed)
+    /// when the matching leaf node is evaluated, unless
+    /// [`keep_variables`](Self::keep_variables) is set.*
+    pub fn insert(&mut self, key: K, value: RoaringBitmap)
+    where
+        K: Hash + Eq,
+    {
+        self.variables.insert(key, value);
+    }
+}
+
+impl<K: Hash + Eq> Evaluator<K, RoaringBitmap, ()> for RoaringEval<K> {
+    fn get_universal(&mut self) -> Result<RoaringBitmap, ()> {
+        Ok(self.universe.clone())
+    }
+
+    fn get_empty(&mut self) -> Result<RoaringBitmap, ()> {
+        Ok(RoaringBitmap::new())
+    }
+
+    fn eval_set(&mut self, key: &K) -> Result<RoaringBitmap, ()> {
+        let value = if self.keep_variables {
+            self.variables.get(key).cloned()
+        } else {
+            self.variables.remove(key)
+        };
+        Ok(value.unwrap_or_default())
+    }
+
+    fn eval_union<'a, I>(&mut self, values: I) -> Result<RoaringBitmap, ()>
+    where
+        I: IntoIterator<Item = &'a RoaringBitmap>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let mut iter = values.into_iter();
+        let first = iter.next().cloned().unwrap_or_default();
+        self.eval_union_owned(first, iter)
+    }
+
+    fn eval_union_owned<'a, I>(
+        &mut self,
+        mut first: RoaringBitmap,
+        rest: I,
+    ) -> Result<RoaringBitmap, ()>
+    where
+        I: IntoIterator<Item = &'a RoaringBitmap>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        for item in rest {
+            first |= item;
+        }
+        Ok(first)
+    }
+
+    fn eval_intersection<'a, I>(&mut self, values: I) -> Result<RoaringBitmap, ()>
+    where
+        I: IntoIterator<Item = &'a RoaringBitmap>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let mut iter = values.into_iter();
+        let Some(first) = iter.next() else {
+            return self.get_universal();
+        };
+        self.eval_intersection_owned(first.clone(), iter)
+    }
+
+    fn eval_intersection_owned<'a, I>(
+        &mut self,
+        mut first: RoaringBitmap,
+        rest: I,
+    ) -> Result<RoaringBitmap, ()>
+    where
+        I: IntoIterator<Item = &'a RoaringBitmap>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        for item in rest {
+            if first.is_empty() {
+                break;
+            }
+            first &= item;
+        }
+        Ok(first)
+    }
+
+    fn eval_difference(
+        &mut self,
+        include: &RoaringBitmap,
+        exclude: &RoaringBitmap,
+    ) -> Result<RoaringBitmap, ()> {
+        Ok(include - exclude)
+    }
+
+    fn eval_complement(&mut self, value: &RoaringBitmap) -> Result<Option<RoaringBitmap>, ()> {
+        Ok(Some(&self.universe - value))
+    }
+
+    fn estimate_size(&self, value: &RoaringBitmap) -> usize {
+        value.len() as usize
+    }
+}
@@ -0,0 +1,422 @@
+use std::{
+    cell::RefCell,
+    cmp::{Ordering, Reverse},
+    collections::{BinaryHeap, VecDeque},
+    iter::Peekable,
+    rc::Rc,
+};
+
+use crate::expr::{Expression, Node, NodeId};
+
+/// A boxed, lazily-evaluated stream of items.
+pub type ItemStream<'a, I> = Box<dyn Iterator<Item = I> + 'a>;
+
+/// Resolves logic into lazy streams of items instead of materialized results.
+///
+/// This is the streaming counterpart of [`Evaluator`](crate::Evaluator). Every stream must
+/// yield items in **strictly ascending** order (like a sorted posting list), which lets the
+/// default combinators merge, intersect, and subtract them lazily. The first results can be
+/// produced before any leaf is fully read.
+///
+/// Only the leaves and the Universal set are required. The set operations have sorted-merge
+/// defaults, and may be overridden with domain-specific versions (e.g. skip lists).
+pub trait StreamEvaluator<'a, T, I: Ord + 'a> {
+    /// Streams the items of the Universal set.
+    ///
+    /// Only used for negations that have nothing to be subtracted from (e.g., a root of `!A`).
+    fn stream_universal(&mut self) -> ItemStream<'a, I>;
+
+    /// Streams the items of a single leaf.
+    fn stream_set(&mut self, set: &T) -> ItemStream<'a, I>;
+
+    /// Lazily merges streams, yielding items present in **at least one** of them.
+    fn stream_union(&mut self, streams: Vec<ItemStream<'a, I>>) -> ItemStream<'a, I> {
+        Box::new(SortedUnion::new(streams))
+    }
+
+    /// Lazily intersects streams, yielding items present in **all** of them.
+    fn stream_intersection(&mut self, streams: Vec<ItemStream<'a, I>>) -> ItemStream<'a, I> {
+        Box::new(SortedIntersection { streams })
+    }
+
+    /// Lazily yields the items of `include` that are not in `exclude`.
+    fn stream_difference(
+        &mut self,
+        include: ItemStream<'a, I>,
+        exclude: ItemStream<'a, I>,
+    ) -> ItemStream<'a, I> {
+        Box::new(SortedDifference {
+            include,
+            exclude: exclude.peekable(),
+        })
+    }
+}
+
+impl<T, Meta> Expression<T, Meta> {
+    /// Composes the single root into one lazy output stream.
+    ///
+    /// The stream graph is wired up front, but no items are pulled until the result is
+    /// iterated. A node used by several parents is streamed once, and its items are buffered
+    /// until every parent has read them, so each leaf is opened at most once (the Universal
+    /// set is opened once per negation). Wiring walks the graph without recursion, but each
+    /// pulled item passes through one stream per level of nesting, so check
+    /// [`depth`](Self::depth) before streaming very deep, untrusted expressions.
+    ///
+    /// # Panics
+    /// Panics if the expression does not have exactly one root.
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::collections::HashMap;
+    /// use logify::{ExpressionBuilder, eval::{ItemStream, StreamEvaluator}};
+    ///
+    /// // Sorted posting lists of document ids
+    /// struct Index(HashMap<&'static str, Vec<u32>>);
+    ///
+    /// impl<'a> StreamEvaluator<'a, &'static str, u32> for &'a Index {
+    ///     fn stream_universal(&mut self) -> ItemStream<'a, u32> {
+    ///         Box::new(0..10)
+    ///     }
+    ///     fn stream_set(&mut self, term: &&'static str) -> ItemStream<'a, u32> {
+    ///         Box::new(self.0[term].iter().copied())
+    ///     }
+    /// }
+    ///
+    /// let builder = ExpressionBuilder::new();
+    /// builder.add_root((builder.leaf("red") | builder.leaf("blue")) & !builder.leaf("sold"));
+    /// let expr = builder.build();
+    ///
+    /// let index = Index(HashMap::from([
+    ///     ("red", vec![1, 4, 7]),
+    ///     ("blue", vec![2, 4, 8]),
+    ///     ("sold", vec![2, 7]),
+    /// ]));
+    ///
+    /// let mut stream = expr.evaluate_stream(&mut &index);
+    /// assert_eq!(stream.next(), Some(1)); // produced without reading the rest
+    /// assert_eq!(stream.collect::<Vec<_>>(), vec![4, 8]);
+    /// ```
+    ///
+    /// Each XOR uses both of its operands twice, yet every leaf is still read once:
+    /// ```rust
+    /// use logify::{Expression, eval::{ItemStream, StreamEvaluator}};
+    ///
+    /// // leaf `d` holds the multiples of `d` below 1000
+    /// struct Multiples;
+    ///
+    /// impl StreamEvaluator<'_, u32, u32> for Multiples {
+    ///     fn stream_universal(&mut self) -> ItemStream<'static, u32> {
+    ///         Box::new(0..1000)
+    ///     }
+    ///     fn stream_set(&mut self, d: &u32) -> ItemStream<'static, u32> {
+    ///         Box::new((0..1000).step_by(*d as usize))
+    ///     }
+    /// }
+    ///
+    /// let mut expr = Expression::new();
+    /// let mut odd = expr.set(2);
+    /// for d in 3..22 {
+    ///     let leaf = expr.set(d);
+    ///     odd = expr.xor(odd, leaf);
+    /// }
+    /// expr.add_root(odd);
+    ///
+    /// // the items with an odd number of divisors in 2..22
+    /// let expected: Vec<u32> = (0..1000)
+    ///     .filter(|i| (2..22).filter(|d| i % d == 0).count() % 2 == 1)
+    ///     .collect();
+    /// assert_eq!(expr.evaluate_stream(&mut Multiples).collect::<Vec<_>>(), expected);
+    /// ```
+    pub fn evaluate_stream<'a, I, S>(&self, solver: &mut S) -> ItemStream<'a, I>
+    where
+        I: Ord + Clone + 'a,
+        S: StreamEvaluator<'a, T, I>,
+    {
+        if self.roots.len() != 1 {
+            panic!(
+                "evaluate_stream requires exactly one root, but the expression has {}.",
+                self.roots.len(),
+            );
+        }
+        self.stream_root(self.roots[0], solver)
+    }
+
+    // wires up every live node below the root, children first, so no recursion is needed
+    fn stream_root<'a, I, S>(&self, root: NodeId, solver: &mut S) -> ItemStream<'a, I>
+    where
+        I: Ord + Clone + 'a,
+        S: StreamEvaluator<'a, T, I>,
+    {
+        // count the parents of each node, so shared ones are read once and buffered
+        let (active, _) = self.get_active_from(&[root]);
+        let mut uses = vec![0usize; root.idx() + 1];
+        uses[root.idx()] += 1;
+        for (idx, node) in self.nodes.iter().enumerate().take(root.idx() + 1) {
+            if let (true, Node::Union(kids) | Node::Intersection(kids)) = (active[idx], node) {
+                for k in kids {
+                    uses[k.idx()] += 1;
+                }
+            }
+        }
+
+        let mut slots: Vec<Option<Slot<'a, I>>> = (0..=root.idx()).map(|_| None).collect();
+        for idx in 1..=root.idx() {
+            if !active[idx] {
+                continue;
+            }
+            let stream = match &self.nodes[idx] {
+                Node::Empty => Box::new(std::iter::empty()),
+                Node::Set(set) => solver.stream_set(set),
+                Node::Union(kids) => {
+                    let streams = kids.iter().map(|&k| take(&mut slots, k, solver)).collect();
+                    solver.stream_union(streams)
+                }
+                Node::Intersection(kids) => {
+                    // A&B&C'&D' == (A&B)-(C|D)
+                    let mut include = Vec::new();
+                    let mut exclude = Vec::new();
+                    for &k in kids {
+                        if k.is_neg() {
+                            exclude.push(take(&mut slots, k.not(), solver));
+                        } else {
+                            include.push(take(&mut slots, k, solver));
+                        }
+                    }
+
+                    let include = match include.len() {
+                        0 => solver.stream_universal(),
+                        1 => include.pop().unwrap(),
+                        _ => solver.stream_intersection(include),
+                    };
+                    match exclude.len() {
+                        0 => include,
+                        1 => solver.stream_difference(include, exclude.pop().unwrap()),
+                        _ => {
+                            let exclude = solver.stream_union(exclude);
+                            solver.stream_difference(include, exclude)
+                        }
+                    }
+                }
+            };
+            slots[idx] = Some(if uses[idx] > 1 {
+                Slot::Shared(Rc::new(RefCell::new(TeeSource::new(stream, uses[idx]))), 0)
+            } else {
+                Slot::Single(stream)
+            });
+        }
+        take(&mut slots, root, solver)
+    }
+}
+
+// the wired-up stream of a node, waiting for its parents
+enum Slot<'a, I> {
+    Single(ItemStream<'a, I>),
+    Shared(Rc<RefCell<TeeSource<'a, I>>>, usize), // and the next reader to hand out
+}
+
+// hands out one use of a node's stream, subtracting it from the Universal set if negated
+fn take<'a, T, I, S>(
+    slots: &mut [Option<Slot<'a, I>>],
+    id: NodeId,
+    solver: &mut S,
+) -> ItemStream<'a, I>
+where
+    I: Ord + Clone + 'a,
+    S: StreamEvaluator<'a, T, I>,
+{
+    if id == NodeId::UNIVERSAL {
+        return solver.stream_universal();
+    }
+    let stream: ItemStream<'a, I> = match slots[id.idx()].take() {
+        None => Box::new(std::iter::empty()), // the empty set
+        Some(Slot::Single(stream)) => stream,
+        Some(Slot::Shared(source, reader)) => {
+            let tee = Tee {
+                source: Rc::clone(&source),
+                reader,
+            };
+            slots[id.idx()] = Some(Slot::Shared(source, reader + 1));
+            Box::new(tee)
+        }
+    };
+    if id.is_neg() {
+        let universal = solver.stream_universal();
+        solver.stream_difference(universal, stream)
+    } else {
+        stream
+    }
+}
+
+// a stream read by several parents, buffering the items the slowest of them hasn't reached
+struct TeeSource<'a, I> {
+    source: ItemStream<'a, I>,
+    buffer: VecDeque<I>,
+    start: usize,             // position of the first buffered item
+    positions: Vec<usize>,    // next position of each reader
+    waiting: VecDeque<usize>, // readers at each position from `start`, one past the buffer
+}
+
+impl<'a, I: Clone> TeeSource<'a, I> {
+    // every reader is registered up front, as merging streams may pull items while wiring
+    fn new(source: ItemStream<'a, I>, readers: usize) -> Self {
+        Self {
+            source,
+            buffer: VecDeque::new(),
+            start: 0,
+            positions: vec![0; readers],
+            waiting: VecDeque::from([readers]),
+        }
+    }
+
+    fn next(&mut self, reader: usize) -> Option<I> {
+        let pos = self.positions[reader];
+        let offset = pos - self.start;
+        let item = match self.buffer.get(offset) {
+            Some(item) => item.clone(),
+            None => {
+                let item = self.source.next()?;
+                self.buffer.push_back(item.clone());
+                self.waiting.push_back(0);
+                item
+            }
+        };
+        self.positions[reader] = pos + 1;
+        self.waiting[offset + 1] += 1;
+        self.leave(offset);
+        Some(item)
+    }
+}
+
+impl<I> TeeSource<'_, I> {
+    // moves a reader off a position, dropping the items every reader has passed
+    fn leave(&mut self, offset: usize) {
+        self.waiting[offset] -= 1;
+        while self.waiting[0] == 0 && self.buffer.pop_front().is_some() {
+            self.waiting.pop_front();
+            self.start += 1;
+        }
+    }
+}
+
+struct Tee<'a, I> {
+    source: Rc<RefCell<TeeSource<'a, I>>>,
+    reader: usize,
+}
+
+impl<I: Clone> Iterator for Tee<'_, I> {
+    type Item = I;
+    fn next(&mut self) -> Option<I> {
+        self.source.borrow_mut().next(self.reader)
+    }
+}
+
+impl<I> Drop for Tee<'_, I> {
+    fn drop(&mut self) {
+        let mut shared = self.source.borrow_mut();
+        let offset = shared.positions[self.reader] - shared.start;
+        shared.leave(offset);
+    }
+}
+
+// k-way merge of ascending streams, dropping duplicates
+struct SortedUnion<'a, I> {
+    streams: Vec<ItemStream<'a, I>>,
+    heap: BinaryHeap<Reverse<(I, usize)>>,
+    started: bool, // the heap is filled on the first pull, so wiring reads nothing
+}
+
+impl<'a, I: Ord> SortedUnion<'a, I> {
+    fn new(streams: Vec<ItemStream<'a, I>>) -> Self {
+        Self {
+            heap: BinaryHeap::with_capacity(streams.len()),
+            streams,
+            started: false,
+        }
+    }
+
+    fn refill(&mut self, i: usize) {
+        if let Some(item) = self.streams[i].next() {
+            self.heap.push(Reverse((item, i)));
+        }
+    }
+}
+
+impl<I: Ord> Iterator for SortedUnion<'_, I> {
+    type Item = I;
+    fn next(&mut self) -> Option<I> {
+        if !self.started {
+            self.started = true;
+            for i in 0..self.streams.len() {
+                self.refill(i);
+            }
+        }
+        let Reverse((item, i)) = self.heap.pop()?;
+        self.refill(i);
+        // skip the same item in other streams
+        while let Some(Reverse((next, _))) = self.heap.peek() {
+            if *next != item {
+                break;
+            }
+            let Reverse((_, j)) = self.heap.pop().unwrap();
+            self.refill(j);
+        }
+        Some(item)
+    }
+}
+
+// leapfrog intersection of ascending streams
+struct SortedIntersection<'a, I> {
+    streams: Vec<ItemStream<'a, I>>,
+}
+
+impl<I: Ord> Iterator for SortedIntersection<'_, I> {
+    type Item = I;
+    fn next(&mut self) -> Option<I> {
+        let n = self.streams.len();
+        let mut target = self.streams.first_mut()?.next()?;
+        let mut matched = 1;
+        let mut i = 1 % n;
+        while matched < n {
+            // advance stream i up to the target
+            loop {
+                let item = self.streams[i].next()?;
+                match item.cmp(&target) {
+                    Ordering::Less => continue,
+                    Ordering::Equal => matched += 1,
+                    Ordering::Greater => {
+                        target = item;
+                        matched = 1;
+                    }
+                }
+                break;
+            }
+            i = (i + 1) % n;
+        }
+        Some(target)
+    }
+}
+
+// items of an ascending stream missing from another
+struct SortedDifference<'a, I> {
+    include: ItemStream<'a, I>,
+    exclude: Peekable<ItemStream<'a, I>>,
+}
+
+impl<I: Ord> Iterator for SortedDifference<'_, I> {
+    type Item = I;
+    fn next(&mut self) -> Option<I> {
+        'outer: loop {
+            let item = self.include.next()?;
+            while let Some(ex) = self.exclude.peek() {
+                match ex.cmp(&item) {
+                    Ordering::Less => {
+                        self.exclude.next();
+                    }
+                    Ordering::Equal => continue 'outer,
+                    Ordering::Greater => break,
+                }
+            }
+            return Some(item);
+        }
+    }
+}
@@ -1,13 +1,26 @@
-use std::hash::{BuildHasher, Hash, Hasher};
+use std::{
+    fmt,
+    hash::{BuildHasher, Hash, Hasher},
+};
 
 use hashbrown::{HashMap, hash_map::RawEntryMut};
 use rapidhash::quality::RandomState;
 use serde::{Deserialize, Serialize};
 
+mod analysis;
 mod basic;
+mod cnf;
 mod convert;
+mod intern;
 mod iter;
 mod ops;
+mod tiny;
+
+pub use analysis::{ExprDiff, ExprStats, NecessaryTerms, RootSummary};
+pub use cnf::{Cnf, Literal};
+pub use tiny::TinyExpr;
+
+use intern::InternState;
 
 /// A handle to a node within an [`Expression`].
 ///
@@ -21,7 +34,7 @@ mod ops;
 ///
 /// *Note: Because the LSB is used for negation, the maximum number of unique nodes
 /// in a single Expression is `u32::MAX / 2`.*
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[cfg_attr(feature = "fast-binary", derive(bitcode::Encode, bitcode::Decode))]
 #[repr(transparent)]
 pub struct NodeId(u32);
@@ -48,6 +61,59 @@ impl NodeId {
     pub(crate) fn not(&self) -> Self {
         Self(self.0 ^ 1)
     }
+
+    /// Returns the position of the referenced node in [`Expression::nodes`].
+    ///
+    /// A node and its negation share an index.
+    ///
+    /// # Example
+    /// ```rust
+    /// let mut expr = logify::Expression::new();
+    /// let a = expr.set("A");
+    /// let not_a = a.negated();
+    ///
+    /// assert_eq!(a.index(), not_a.index());
+    /// assert!(matches!(expr.nodes().nth(a.index()), Some(logify::expr::Node::Set("A"))));
+    /// assert!(!a.is_negated() && not_a.is_negated());
+    /// assert_eq!(not_a.negated(), a);
+    /// ```
+    pub fn index(&self) -> usize {
+        self.idx()
+    }
+
+    /// Returns `true` if this ID refers to the complement of its node.
+    pub fn is_negated(&self) -> bool {
+        self.is_neg()
+    }
+
+    /// Returns the ID of the complement, flipping only the sign.
+    ///
+    /// The same as [`Expression::complement`], without needing the expression at hand.
+    pub fn negated(&self) -> Self {
+        self.not()
+    }
+}
+
+/// Prints the node index and sign, e.g. `#3` or `!#3`.
+///
+/// ```rust
+/// let mut expr = logify::Expression::new();
+/// let a = expr.set("A");
+/// let not_a = expr.complement(a);
+///
+/// assert_eq!(format!("{:?}", [a, not_a]), "[#1, !#1]");
+/// assert_eq!(format!("{:?}", logify::NodeId::UNIVERSAL), "UNIVERSAL");
+/// ```
+impl fmt::Debug for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::EMPTY => write!(f, "EMPTY"),
+            Self::UNIVERSAL => write!(f, "UNIVERSAL"),
+            Self::MAX => write!(f, "MAX"),
+            _ if self.is_neg() => write!(f, "!#{}", self.idx()),
+            _ => write!(f, "#{}", self.idx()),
+        }
+    }
 }
 
 /// Stores the logic or the term.
@@ -68,6 +134,114 @@ pub enum Node<T> {
     Intersection(Vec<NodeId>),
 }
 
+/// A constant value, as returned by [`Expression::resolve_constant`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConstKind {
+    /// The empty set (always false).
+    Empty,
+    /// The universal set (always true).
+    Universal,
+}
+
+/// An error returned when a [`NodeId`] cannot be used for the requested edit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExprError {
+    /// The ID does not belong to this expression.
+    InvalidNode,
+    /// The node is not a Union or Intersection.
+    NotAGroup,
+}
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExprError::InvalidNode => write!(f, "node does not exist in this expression"),
+            ExprError::NotAGroup => write!(f, "expected a Union or Intersection node"),
+        }
+    }
+}
+
+impl std::error::Error for ExprError {}
+
+/// An error returned when deserialized data does not form a valid expression.
+///
+/// Deserialization checks the graph invariants that the smart constructors guarantee, so
+/// corrupted or hand-crafted data is rejected up front instead of looping or panicking during
+/// evaluation or optimization.
+#[derive(Debug)]
+pub enum DecodeError {
+    /// The bitcode bytes could not be decoded at all.
+    #[cfg(feature = "fast-binary")]
+    Encoding(bitcode::Error),
+    /// The node list does not start with the Empty node.
+    MissingEmpty,
+    /// A group references a child that does not come before it, which could form a cycle.
+    ForwardReference {
+        /// Index of the group.
+        node: usize,
+        /// Index of the offending child.
+        child: usize,
+    },
+    /// A group has no children, which the smart constructors fold into a constant.
+    EmptyGroup {
+        /// Index of the group.
+        node: usize,
+    },
+    /// A root points past the end of the node list.
+    RootOutOfRange {
+        /// Index of the root.
+        root: usize,
+    },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "fast-binary")]
+            DecodeError::Encoding(_) => write!(f, "expression data could not be decoded"),
+            DecodeError::MissingEmpty => write!(f, "node 0 is not the Empty node"),
+            DecodeError::ForwardReference { node, child } => {
+                write!(
+                    f,
+                    "node {} references node {}, which does not precede it",
+                    node, child
+                )
+            }
+            DecodeError::EmptyGroup { node } => {
+                write!(f, "node {} is a group with no children", node)
+            }
+            DecodeError::RootOutOfRange { root } => {
+                write!(f, "root {} points past the end of the node list", root)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "fast-binary")]
+            DecodeError::Encoding(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// An error returned by [`Expression::to_dnf`] when the expansion grows past its limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DnfTooLarge {
+    /// The limit that was exceeded.
+    pub max_terms: usize,
+}
+
+impl fmt::Display for DnfTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DNF expansion exceeded {} products", self.max_terms)
+    }
+}
+
+impl std::error::Error for DnfTooLarge {}
+
 /// A self-contained, optimized Boolean logic graph.
 ///
 /// `Expression` stores logic in a deduplicated Directed Acyclic Graph (DAG). It is the
@@ -78,6 +252,10 @@ pub enum Node<T> {
 /// * **Flat Memory:** Nodes are stored in a dense `Vec`, improving CPU cache locality.
 /// * **Safe:** Constructed via append-only logic, ensuring no cycles exist.
 ///
+/// Interning hashes every new node with rapidhash's quality variant. For trusted terms, such
+/// as small integers, [`with_fast_hash`](Self::with_fast_hash) switches a single expression
+/// to its faster variant; see the `build` benchmark to compare the two.
+///
 /// # Example: Boolean Evaluation
 ///
 /// This example builds a simple logic gate (`A AND NOT B`) and evaluates it against
@@ -142,25 +320,53 @@ pub enum Node<T> {
 /// let results_2 = expr.evaluate(&mut solver_2).unwrap();
 /// assert_eq!(results_2[0], false);
 /// ```
+///
+/// # Root Metadata
+/// The optional `Meta` parameter attaches a value to every root, such as a rule ID or a
+/// priority. It is kept aligned with the roots through optimization, pruning, merging, and
+/// serialization, so it can be looked up by root index with [`root_meta`](Self::root_meta).
+/// It defaults to `()`, which costs nothing.
+///
+/// # Serialization
+/// With serde, the node list, roots, and root metadata are written as they are stored. Terms
+/// are interned like any other node, so each distinct term lives in exactly one `Set` node
+/// and is written once, however many groups use it; groups refer to it by index. Long,
+/// repetitive terms therefore need no separate term table.
+///
+/// Since root metadata was added, serde output always has a `meta` list with one entry per
+/// root (a list of `null`s in JSON when `Meta` is `()`). Data written before then has no `meta`
+/// field and still loads, with every root getting `Meta::default()`. The `fast-binary` format
+/// is versioned, so older bitcode blobs load the same way.
+///
+/// ```rust
+/// let mut expr = logify::Expression::new();
+/// let tag = expr.set("a-fairly-long-tag-name".to_string());
+/// let again = expr.set("a-fairly-long-tag-name".to_string());
+/// assert_eq!(tag, again);
+/// assert_eq!(expr.node_count(), 2); // Empty and the one Set
+/// ```
 #[derive(Serialize, Deserialize)]
-#[serde(from = "ExpressionShadow<T>")]
-#[serde(bound = "T: Serialize + for<'a> Deserialize<'a> + Hash + PartialEq")]
+#[serde(try_from = "ExpressionShadow<T, Meta>")]
+#[serde(bound = "T: Serialize + for<'a> Deserialize<'a> + Hash + PartialEq, \
+    Meta: Serialize + for<'a> Deserialize<'a> + Default")]
 #[cfg_attr(feature = "fast-binary", derive(bitcode::Encode))]
-pub struct Expression<T> {
+pub struct Expression<T, Meta = ()> {
     pub(crate) nodes: Vec<Node<T>>,
     pub(crate) roots: Vec<NodeId>,
+    pub(crate) meta: Vec<Meta>,
     #[serde(skip, default = "default_cache")]
     #[cfg_attr(feature = "fast-binary", bitcode(skip))]
-    pub(crate) cache: HashMap<NodeId, (), RandomState>,
+    pub(crate) cache: HashMap<NodeId, (), InternState>,
     pub(crate) uuid: u128,
     pub(crate) generation: u64,
 }
 
-impl<T> Default for Expression<T> {
+impl<T, Meta> Default for Expression<T, Meta> {
     fn default() -> Self {
         Self {
             nodes: vec![Node::Empty], // begin with Empty node 0
             roots: Vec::new(),
+            meta: Vec::new(),
             cache: default_cache(),
             uuid: generate_uuid(),
             generation: 0,
@@ -168,13 +374,14 @@ impl<T> Default for Expression<T> {
     }
 }
 
-impl<T: Clone + Hash + PartialEq> Clone for Expression<T> {
+impl<T: Clone + Hash + PartialEq, Meta: Clone> Clone for Expression<T, Meta> {
     fn clone(&self) -> Self {
         let nodes = self.nodes.clone();
-        let cache = build_cache(&nodes);
+        let cache = build_cache(&nodes, self.cache.hasher().reseeded());
         Self {
             nodes,
             roots: self.roots.clone(),
+            meta: self.meta.clone(),
             cache,
             uuid: generate_uuid(),
             generation: self.generation,
@@ -182,8 +389,8 @@ impl<T: Clone + Hash + PartialEq> Clone for Expression<T> {
     }
 }
 
-fn default_cache() -> HashMap<NodeId, (), RandomState> {
-    HashMap::with_hasher(RandomState::new())
+fn default_cache() -> HashMap<NodeId, (), InternState> {
+    HashMap::with_hasher(InternState::default())
 }
 
 fn generate_uuid() -> u128 {
@@ -202,29 +409,71 @@ fn generate_uuid() -> u128 {
 
 #[derive(Deserialize)]
 #[cfg_attr(feature = "fast-binary", derive(bitcode::Decode))]
-struct ExpressionShadow<T> {
+struct ExpressionShadow<T, Meta> {
     nodes: Vec<Node<T>>,
     roots: Vec<NodeId>,
+    // missing from data written before root metadata existed
+    #[serde(default)]
+    meta: Vec<Meta>,
     uuid: u128,
     generation: u64,
 }
 
-impl<T: Hash + PartialEq> From<ExpressionShadow<T>> for Expression<T> {
-    fn from(value: ExpressionShadow<T>) -> Self {
+impl<T, Meta> ExpressionShadow<T, Meta> {
+    // the invariants the smart constructors guarantee, which the data can't be trusted to keep
+    fn validate(&self) -> Result<(), DecodeError> {
+        if !matches!(self.nodes.first(), Some(Node::Empty)) {
+            return Err(DecodeError::MissingEmpty);
+        }
+        for (idx, node) in self.nodes.iter().enumerate() {
+            let (Node::Union(kids) | Node::Intersection(kids)) = node else {
+                continue;
+            };
+            // evaluators combine a group's results starting from its first child
+            if kids.is_empty() {
+                return Err(DecodeError::EmptyGroup { node: idx });
+            }
+            // children must come first, which also rules out cycles
+            if let Some(kid) = kids.iter().find(|k| k.idx() >= idx) {
+                return Err(DecodeError::ForwardReference {
+                    node: idx,
+                    child: kid.idx(),
+                });
+            }
+        }
+        if let Some(root) = self.roots.iter().position(|r| r.idx() >= self.nodes.len()) {
+            return Err(DecodeError::RootOutOfRange { root });
+        }
+        Ok(())
+    }
+}
+
+impl<T: Hash + PartialEq, Meta: Default> TryFrom<ExpressionShadow<T, Meta>>
+    for Expression<T, Meta>
+{
+    type Error = DecodeError;
+
+    fn try_from(mut value: ExpressionShadow<T, Meta>) -> Result<Self, DecodeError> {
+        value.validate()?;
         // TODO: this won't build with the wrong location if it's in ExpressionShadow, will it?
-        let cache = build_cache(&value.nodes);
-        Self {
+        let cache = build_cache(&value.nodes, InternState::default());
+        value.meta.resize_with(value.roots.len(), Meta::default);
+        Ok(Self {
             nodes: value.nodes,
             roots: value.roots,
+            meta: value.meta,
             cache,
             uuid: value.uuid,
             generation: value.generation,
-        }
+        })
     }
 }
 
-fn build_cache<T: Hash + PartialEq>(nodes: &[Node<T>]) -> HashMap<NodeId, (), RandomState> {
-    let mut cache = HashMap::with_hasher(RandomState::new());
+fn build_cache<T: Hash + PartialEq>(
+    nodes: &[Node<T>],
+    state: InternState,
+) -> HashMap<NodeId, (), InternState> {
+    let mut cache = HashMap::with_hasher(state);
     let hasher_builder = *cache.hasher();
     for (i, node) in nodes.iter().enumerate() {
         if let Node::Empty = node {
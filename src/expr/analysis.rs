@@ -0,0 +1,879 @@
+use std::hash::{Hash, Hasher};
+
+use rapidhash::quality::RapidHasher;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    eval::BoolEval,
+    expr::{ConstKind, Expression, Node, NodeId},
+};
+
+/// Counts of roots by constant value, as returned by [`Expression::root_constant_summary`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RootSummary {
+    /// Roots that are the Universal set.
+    pub always_true: usize,
+    /// Roots that are the Empty set.
+    pub always_false: usize,
+    /// Roots that still depend on their terms.
+    pub conditional: usize,
+}
+
+/// Node counts for an expression, as returned by [`Expression::stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExprStats {
+    /// Live `Set` (leaf) nodes.
+    pub sets: usize,
+    /// Live `Union` nodes.
+    pub unions: usize,
+    /// Live `Intersection` nodes.
+    pub intersections: usize,
+    /// References to leaves from live groups and roots, counting each use.
+    pub literals: usize,
+    /// Number of roots.
+    pub roots: usize,
+    /// Stored nodes no root can reach, excluding the constant node.
+    pub dead: usize,
+}
+
+/// Root-level changes between two expressions, as returned by [`Expression::diff`].
+///
+/// Roots are matched by position. Indices in `removed` refer to the old expression, all
+/// others to the new one.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExprDiff {
+    /// Roots only in the new expression.
+    pub added: Vec<usize>,
+    /// Roots only in the old expression.
+    pub removed: Vec<usize>,
+    /// Roots in both whose logic differs structurally.
+    pub changed: Vec<usize>,
+    /// Roots in both with structurally identical logic.
+    pub unchanged: Vec<usize>,
+}
+
+impl ExprDiff {
+    /// Returns `true` if no root was added, removed, or changed.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// The terms fixed across every satisfying assignment, as returned by
+/// [`Expression::necessary_terms`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NecessaryTerms<T> {
+    /// Terms present in every assignment that makes the root true.
+    pub present: Vec<T>,
+    /// Terms absent from every assignment that makes the root true.
+    pub absent: Vec<T>,
+}
+
+impl<T, Meta> Expression<T, Meta> {
+    /// Returns `true` if the roots at index `a` and `b` reference any common node.
+    ///
+    /// The constant Empty/Universal node is ignored, as it holds no work to share.
+    /// Roots sharing nodes cannot be evaluated independently without coordinating
+    /// access to the shared intermediate results.
+    ///
+    /// # Panics
+    /// Panics if `a` or `b` is not a valid root index.
+    ///
+    /// # Example
+    /// ```rust
+    /// let mut expr = logify::Expression::new();
+    /// let a = expr.set("A");
+    /// let b = expr.set("B");
+    /// let c = expr.set("C");
+    /// let ab = expr.union([a, b]);
+    /// let ab_c = expr.intersection([ab, c]);
+    ///
+    /// expr.add_root(ab);
+    /// expr.add_root(ab_c);
+    /// let not_c = expr.complement(c);
+    /// expr.add_root(not_c);
+    ///
+    /// assert!(expr.roots_share_nodes(0, 1)); // both use (A | B)
+    /// assert!(!expr.roots_share_nodes(0, 2));
+    /// ```
+    pub fn roots_share_nodes(&self, a: usize, b: usize) -> bool {
+        let (root_a, root_b) = (self.roots[a], self.roots[b]);
+
+        // mark every node reachable from a
+        let mut marked = vec![false; self.nodes.len()];
+        let mut stack = vec![root_a];
+        while let Some(id) = stack.pop() {
+            if id.idx() == 0 || marked[id.idx()] {
+                continue;
+            }
+            marked[id.idx()] = true;
+            if let Node::Union(kids) | Node::Intersection(kids) = &self.nodes[id.idx()] {
+                stack.extend_from_slice(kids);
+            }
+        }
+
+        // search b for a marked node
+        let mut visited = vec![false; self.nodes.len()];
+        let mut stack = vec![root_b];
+        while let Some(id) = stack.pop() {
+            if id.idx() == 0 || visited[id.idx()] {
+                continue;
+            }
+            if marked[id.idx()] {
+                return true;
+            }
+            visited[id.idx()] = true;
+            if let Node::Union(kids) | Node::Intersection(kids) = &self.nodes[id.idx()] {
+                stack.extend_from_slice(kids);
+            }
+        }
+        false
+    }
+
+    /// Partitions the roots into groups that share no nodes with any other group.
+    ///
+    /// Each group holds root indices in ascending order, and groups are ordered by their
+    /// first root. Roots in different groups can be evaluated on separate threads without
+    /// coordination. The constant Empty/Universal node is ignored.
+    ///
+    /// # Example
+    /// ```rust
+    /// let mut expr = logify::Expression::new();
+    /// let a = expr.set("A");
+    /// let b = expr.set("B");
+    /// let c = expr.set("C");
+    /// let ab = expr.union([a, b]);
+    ///
+    /// expr.add_root(ab);
+    /// expr.add_root(c);
+    /// let not_a = expr.complement(a);
+    /// expr.add_root(not_a);
+    ///
+    /// assert_eq!(expr.independent_root_groups(), vec![vec![0, 2], vec![1]]);
+    /// ```
+    pub fn independent_root_groups(&self) -> Vec<Vec<usize>> {
+        // union-find over root indices
+        let mut parent: Vec<usize> = (0..self.roots.len()).collect();
+        fn find(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+
+        // claim each node for the first root that reaches it, joining any later root that reaches it too
+        let mut owner = vec![usize::MAX; self.nodes.len()];
+        let mut stack = Vec::new();
+        for (r, &root) in self.roots.iter().enumerate() {
+            stack.clear();
+            stack.push(root);
+            while let Some(id) = stack.pop() {
+                let idx = id.idx();
+                if idx == 0 {
+                    continue;
+                }
+                if owner[idx] != usize::MAX {
+                    // already claimed, the subtree below is owned by the same group
+                    let (a, b) = (find(&mut parent, owner[idx]), find(&mut parent, r));
+                    if a != b {
+                        parent[a.max(b)] = a.min(b);
+                    }
+                    continue;
+                }
+                owner[idx] = r;
+                if let Node::Union(kids) | Node::Intersection(kids) = &self.nodes[idx] {
+                    stack.extend_from_slice(kids);
+                }
+            }
+        }
+
+        // collect groups in order of their first root
+        let mut group_of = vec![usize::MAX; self.roots.len()];
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        for r in 0..self.roots.len() {
+            let rep = find(&mut parent, r);
+            if group_of[rep] == usize::MAX {
+                group_of[rep] = groups.len();
+                groups.push(Vec::new());
+            }
+            groups[group_of[rep]].push(r);
+        }
+        groups
+    }
+
+    /// Iterates the distinct terms reachable from the roots, in node order.
+    ///
+    /// Unlike [`term_set`](Self::term_set), this borrows the terms and needs no bounds on `T`,
+    /// which makes it cheap to call before every evaluation to prefetch the data a rule uses.
+    /// Leaves are interned, so each term is yielded once. Dead nodes, such as those left
+    /// behind by `optimize`, are skipped.
+    ///
+    /// # Example
+    /// ```rust
+    /// let mut expr = logify::Expression::new();
+    /// let b = expr.set("B");
+    /// let a = expr.set("A");
+    /// let _dead = expr.set("C");
+    /// let not_a = expr.complement(a);
+    /// let root = expr.union([b, not_a]);
+    /// expr.add_root(root);
+    ///
+    /// assert_eq!(expr.terms().collect::<Vec<_>>(), [&"B", &"A"]);
+    /// ```
+    pub fn terms(&self) -> impl Iterator<Item = &T> {
+        let (active, max_root) = self.get_active();
+        self.nodes[..=max_root]
+            .iter()
+            .zip(active)
+            .filter_map(|(node, active)| match node {
+                Node::Set(val) if active => Some(val),
+                _ => None,
+            })
+    }
+
+    /// Returns the distinct terms reachable from the roots, sorted.
+    ///
+    /// Useful for registering every variable an expression needs with an evaluator, or
+    /// for building a stable term index. Dead nodes are ignored.
+    ///
+    /// # Example
+    /// ```rust
+    /// let mut expr = logify::Expression::new();
+    /// let b = expr.set("B");
+    /// let a = expr.set("A");
+    /// let _dead = expr.set("C");
+    /// let root = expr.union([b, a]);
+    /// expr.add_root(root);
+    ///
+    /// assert_eq!(expr.term_set(), vec!["A", "B"]);
+    /// ```
+    pub fn term_set(&self) -> Vec<T>
+    where
+        T: Clone + Ord,
+    {
+        let mut terms: Vec<T> = self
+            .iter_dependencies()
+            .filter_map(|(_, node)| match node {
+                Node::Set(val) => Some(val.clone()),
+                _ => None,
+            })
+            .collect();
+        terms.sort_unstable();
+        terms.dedup();
+        terms
+    }
+
+    /// Hashes the distinct live terms, ignoring the logic that connects them.
+    ///
+    /// Two expressions using the same terms hash equal no matter how the terms are combined,
+    /// which is handy for bucketing filters that need the same data loaded. The hash uses a
+    /// fixed seed, so it is stable across runs of the same build.
+    ///
+    /// # Example
+    /// ```rust
+    /// let mut x = logify::Expression::new();
+    /// let (a, b) = (x.set("A"), x.set("B"));
+    /// let root = x.union([a, b]);
+    /// x.add_root(root);
+    ///
+    /// let mut y = logify::Expression::new();
+    /// let (b, a) = (y.set("B"), y.set("A"));
+    /// let not_a = y.complement(a);
+    /// let root = y.intersection([b, not_a]);
+    /// y.add_root(root);
+    ///
+    /// assert_eq!(x.terms_hash(), y.terms_hash());
+    /// ```
+    pub fn terms_hash(&self) -> u64
+    where
+        T: Hash + Ord,
+    {
+        let mut terms: Vec<&T> = self
+            .iter_dependencies()
+            .filter_map(|(_, node)| match node {
+                Node::Set(val) => Some(val),
+                _ => None,
+            })
+            .collect();
+        terms.sort_unstable();
+        terms.dedup();
+
+        let mut hasher = RapidHasher::default();
+        terms.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Hashes the live logic in a way that ignores node numbering and child order.
+    ///
+    /// Each live node is hashed from its term, or from its kind and the sorted hashes of its
+    /// children, so two expressions that are structurally identical hash equal even if they
+    /// were built in a different order or carry different dead nodes. The roots are hashed in
+    /// order. Unlike the per-instance UUID, this is stable across runs of the same build, which
+    /// makes it suitable as a key for caching optimization results.
+    ///
+    /// # Example
+    /// ```rust
+    /// let mut x = logify::Expression::new();
+    /// let (a, b, c) = (x.set("A"), x.set("B"), x.set("C"));
+    /// let ab = x.intersection([a, b]);
+    /// let root = x.union([ab, c]);
+    /// x.add_root(root);
+    ///
+    /// let mut y = logify::Expression::new();
+    /// let (c, b, a) = (y.set("C"), y.set("B"), y.set("A"));
+    /// let _dead = y.set("D");
+    /// let ab = y.intersection([b, a]);
+    /// let root = y.union([c, ab]);
+    /// y.add_root(root);
+    ///
+    /// assert_eq!(x.canonical_hash(), y.canonical_hash());
+    ///
+    /// let not_root = y.complement(root);
+    /// y.rewrite_roots(|_, _| not_root);
+    /// assert_ne!(x.canonical_hash(), y.canonical_hash());
+    /// ```
+    pub fn canonical_hash(&self) -> u64
+    where
+        T: Hash,
+    {
+        let hashes = self.canonical_node_hashes();
+        let mut hasher = RapidHasher::default();
+        self.roots.len().hash(&mut hasher);
+        for root in &self.roots {
+            (hashes[root.idx()], root.is_neg()).hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// Compares the roots of this expression with those of a newer version.
+    ///
+    /// Roots are matched by position, and each pair is compared by the same structural hash
+    /// as [`canonical_hash`](Self::canonical_hash), so node numbering, child order, and dead
+    /// nodes don't count as changes. No logical equivalence check is done: `A | (A & B)`
+    /// and `A` are reported as changed. The result is serializable, for building changelogs.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::ExpressionBuilder;
+    ///
+    /// let old = ExpressionBuilder::<&str>::new();
+    /// old.add_root(old.leaf("Admin") | old.leaf("Owner"));
+    /// old.add_root(old.leaf("Editor"));
+    /// let old = old.build();
+    ///
+    /// let new = ExpressionBuilder::<&str>::new();
+    /// new.add_root(new.leaf("Owner") | new.leaf("Admin"));
+    /// new.add_root(new.leaf("Editor") & !new.leaf("Banned"));
+    /// new.add_root(new.leaf("Viewer"));
+    /// let new = new.build();
+    ///
+    /// let diff = old.diff(&new);
+    /// assert_eq!(diff.unchanged, [0]);
+    /// assert_eq!(diff.changed, [1]);
+    /// assert_eq!(diff.added, [2]);
+    /// assert!(diff.removed.is_empty());
+    /// ```
+    pub fn diff<M2>(&self, other: &Expression<T, M2>) -> ExprDiff
+    where
+        T: Hash,
+    {
+        let old_hashes = self.canonical_node_hashes();
+        let new_hashes = other.canonical_node_hashes();
+        let mut diff = ExprDiff::default();
+        for (i, (old, new)) in self.roots.iter().zip(&other.roots).enumerate() {
+            let old = (old_hashes[old.idx()], old.is_neg());
+            let new = (new_hashes[new.idx()], new.is_neg());
+            if old == new {
+                diff.unchanged.push(i);
+            } else {
+                diff.changed.push(i);
+            }
+        }
+        diff.removed = (other.roots.len()..self.roots.len()).collect();
+        diff.added = (self.roots.len()..other.roots.len()).collect();
+        diff
+    }
+
+    // structural hash of every live node, ignoring numbering and child order
+    fn canonical_node_hashes(&self) -> Vec<u64>
+    where
+        T: Hash,
+    {
+        let (active, max_root) = self.get_active();
+        let mut hashes = vec![0u64; max_root + 1];
+        let mut kids_buf = Vec::new();
+
+        // children come before parents, so their hashes are always ready
+        for idx in 0..=max_root {
+            if !active[idx] {
+                continue;
+            }
+            let mut hasher = RapidHasher::default();
+            let (tag, kids): (u8, &[NodeId]) = match &self.nodes[idx] {
+                Node::Empty => (0, &[]),
+                Node::Set(val) => {
+                    val.hash(&mut hasher);
+                    (1, &[])
+                }
+                Node::Union(kids) => (2, kids),
+                Node::Intersection(kids) => (3, kids),
+            };
+            tag.hash(&mut hasher);
+            kids_buf.clear();
+            kids_buf.extend(kids.iter().map(|k| (hashes[k.idx()], k.is_neg())));
+            kids_buf.sort_unstable();
+            kids_buf.hash(&mut hasher);
+            hashes[idx] = hasher.finish();
+        }
+        hashes
+    }
+
+    /// Counts the live nodes referenced more than once, by parent groups or as a root.
+    ///
+    /// These are the nodes an [`EvaluatorCache`](crate::eval::EvaluatorCache) saves work on:
+    /// each is evaluated once and its result reused by every other reference. The constant
+    /// node is not counted.
+    ///
+    /// # Example
+    /// ```rust
+    /// let mut expr = logify::Expression::new();
+    /// let (a, b, c) = (expr.set("A"), expr.set("B"), expr.set("C"));
+    /// let ab = expr.union([a, b]);
+    /// let abc = expr.intersection([ab, c]);
+    /// let not_b = expr.complement(b);
+    /// let root = expr.union([abc, not_b]);
+    /// expr.add_root(root);
+    ///
+    /// assert_eq!(expr.shared_node_count(), 1); // B
+    /// ```
+    pub fn shared_node_count(&self) -> usize {
+        self.parent_counts()
+            .iter()
+            .skip(1)
+            .filter(|&&n| n > 1)
+            .count()
+    }
+
+    /// Estimates how much deduplication and caching help, as references per live node.
+    ///
+    /// Every child edge and root reference is counted, then divided by the number of distinct
+    /// live nodes, excluding the constant node. A pure tree with one root scores exactly 1.0,
+    /// meaning a persistent cache barely helps; the higher the score, the more each cached
+    /// result is reused. An expression with no live nodes scores 1.0.
+    ///
+    /// # Example
+    /// ```rust
+    /// let mut tree = logify::Expression::new();
+    /// let (a, b) = (tree.set("A"), tree.set("B"));
+    /// let root = tree.union([a, b]);
+    /// tree.add_root(root);
+    /// assert_eq!(tree.cache_benefit_estimate(), 1.0);
+    ///
+    /// // three roots sharing one (A | B)
+    /// let (c, d) = (tree.set("C"), tree.set("D"));
+    /// let ab_c = tree.intersection([root, c]);
+    /// let ab_d = tree.intersection([root, d]);
+    /// tree.add_root(ab_c);
+    /// tree.add_root(ab_d);
+    /// assert!(tree.cache_benefit_estimate() > 1.0);
+    /// ```
+    pub fn cache_benefit_estimate(&self) -> f64 {
+        let counts = self.parent_counts();
+        let live = counts.iter().skip(1).filter(|&&n| n > 0).count();
+        if live == 0 {
+            return 1.0;
+        }
+        let references: usize = counts.iter().skip(1).sum();
+        references as f64 / live as f64
+    }
+
+    /// Returns the number of nodes on the longest path from a root down to a leaf.
+    ///
+    /// A leaf or constant root has depth 1, and each group adds one level. Negations are
+    /// free, as they live on the edge. Shared nodes are measured once, so this stays linear
+    /// in the size of the DAG. An expression with no roots has depth 0.
+    ///
+    /// # Example
+    /// ```rust
+    /// let mut expr = logify::Expression::new();
+    /// let (a, b, c) = (expr.set("A"), expr.set("B"), expr.set("C"));
+    /// let ab = expr.union([a, b]);
+    /// let not_ab = expr.complement(ab);
+    /// let root = expr.intersection([not_ab, c]);
+    /// expr.add_root(c);
+    /// assert_eq!(expr.depth(), 1);
+    ///
+    /// expr.add_root(root);
+    /// assert_eq!(expr.depth(), 3);
+    /// ```
+    pub fn depth(&self) -> usize {
+        let (active, max_root) = self.get_active();
+        let mut depths = vec![0; max_root + 1];
+        for (idx, node) in self.nodes[..=max_root].iter().enumerate() {
+            if !active[idx] {
+                continue;
+            }
+            depths[idx] = match node {
+                Node::Union(kids) | Node::Intersection(kids) => {
+                    1 + kids.iter().map(|k| depths[k.idx()]).max().unwrap_or(0)
+                }
+                _ => 1,
+            };
+        }
+        self.roots
+            .iter()
+            .map(|r| depths[r.idx()])
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the largest number of children held by any live group.
+    ///
+    /// Returns 0 when no group is reachable from the roots.
+    ///
+    /// # Example
+    /// ```rust
+    /// let mut expr = logify::Expression::new();
+    /// let (a, b, c) = (expr.set("A"), expr.set("B"), expr.set("C"));
+    /// let _dead = expr.union([a, b, c]);
+    /// let root = expr.intersection([a, b]);
+    /// expr.add_root(root);
+    ///
+    /// assert_eq!(expr.max_fanout(), 2);
+    /// ```
+    pub fn max_fanout(&self) -> usize {
+        let (active, max_root) = self.get_active();
+        self.nodes[..=max_root]
+            .iter()
+            .zip(active)
+            .filter_map(|(node, active)| match node {
+                Node::Union(kids) | Node::Intersection(kids) if active => Some(kids.len()),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Counts the live nodes by kind, the leaf references, and the dead nodes.
+    ///
+    /// Handy for tracking how much [`optimize`](Self::optimize) and
+    /// [`compress`](Self::compress) shrink a rule set. The constant node is part of every
+    /// expression, so it is never counted.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{Expression, expr::ExprStats};
+    ///
+    /// let mut expr = Expression::new();
+    /// let (a, b, c) = (expr.set("A"), expr.set("B"), expr.set("C"));
+    /// let _dead = expr.union([b, c]);
+    /// let ab = expr.intersection([a, b]);
+    /// let root = expr.union([ab, a]);
+    /// expr.add_root(root);
+    /// expr.add_root(b);
+    ///
+    /// assert_eq!(expr.stats(), ExprStats {
+    ///     sets: 2,
+    ///     unions: 1,
+    ///     intersections: 1,
+    ///     literals: 4, // A twice, B twice
+    ///     roots: 2,
+    ///     dead: 2, // C and (B | C)
+    /// });
+    /// ```
+    pub fn stats(&self) -> ExprStats {
+        let is_leaf = |id: &NodeId| matches!(self.nodes[id.idx()], Node::Set(_));
+        let mut stats = ExprStats {
+            roots: self.roots.len(),
+            literals: self.roots.iter().filter(|r| is_leaf(r)).count(),
+            ..ExprStats::default()
+        };
+        for (_, node) in self.iter_dependencies() {
+            match node {
+                Node::Empty => continue,
+                Node::Set(_) => stats.sets += 1,
+                Node::Union(kids) => {
+                    stats.unions += 1;
+                    stats.literals += kids.iter().filter(|k| is_leaf(k)).count();
+                }
+                Node::Intersection(kids) => {
+                    stats.intersections += 1;
+                    stats.literals += kids.iter().filter(|k| is_leaf(k)).count();
+                }
+            }
+        }
+        let live = stats.sets + stats.unions + stats.intersections;
+        stats.dead = self.node_count().saturating_sub(live + 1);
+        stats
+    }
+
+    // how many root references and live child edges point at each node
+    fn parent_counts(&self) -> Vec<usize> {
+        let (active, max_root) = self.get_active();
+        let mut counts = vec![0; max_root + 1];
+        for root in &self.roots {
+            counts[root.idx()] += 1;
+        }
+        for (node, active) in self.nodes[..=max_root].iter().zip(active) {
+            if let (true, Node::Union(kids) | Node::Intersection(kids)) = (active, node) {
+                for k in kids {
+                    counts[k.idx()] += 1;
+                }
+            }
+        }
+        counts
+    }
+
+    /// Splits a root into its OR branches.
+    ///
+    /// If `root` is a positive Union, its children are returned, each one an alternative
+    /// clause of the rule. Any other node (an Intersection, a leaf, or a negation) is
+    /// returned as a single clause.
+    ///
+    /// # Example
+    /// ```rust
+    /// let mut expr = logify::Expression::new();
+    /// let a = expr.set("A");
+    /// let b = expr.set("B");
+    /// let ab = expr.intersection([a, b]);
+    /// let c = expr.set("C");
+    /// let root = expr.union([ab, c]);
+    ///
+    /// assert_eq!(expr.root_clauses(root), vec![ab, c]);
+    /// assert_eq!(expr.root_clauses(ab), vec![ab]);
+    /// ```
+    pub fn root_clauses(&self, root: NodeId) -> Vec<NodeId> {
+        match &self.nodes[root.idx()] {
+            Node::Union(kids) if !root.is_neg() => kids.clone(),
+            _ => vec![root],
+        }
+    }
+
+    /// Returns the truth value of `root` if it is a constant.
+    ///
+    /// `Some(true)` for `UNIVERSAL` (matches everything), `Some(false)` for `EMPTY` (matches
+    /// nothing), and `None` if the result depends on the terms. Only the ID itself is checked,
+    /// so run [`optimize`](Self::optimize) or [`fold_constants`](Self::fold_constants) first
+    /// for roots that only simplify to a constant.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{ExpressionBuilder, opt::OptimizerConfig};
+    ///
+    /// let builder = ExpressionBuilder::<&str>::new();
+    /// let (a, b) = (builder.leaf("A"), builder.leaf("B"));
+    /// builder.add_root(a | !(a & b) | b);
+    /// builder.add_root(a & !(a | b));
+    /// builder.add_root(a);
+    /// let mut expr = builder.build();
+    /// expr.optimize(&mut OptimizerConfig::default());
+    ///
+    /// let roots = expr.roots_owned();
+    /// assert!(expr.is_tautology(roots[0]));
+    /// assert!(expr.is_contradiction(roots[1]));
+    /// assert_eq!(expr.root_constant(roots[2]), None);
+    /// ```
+    pub fn root_constant(&self, root: NodeId) -> Option<bool> {
+        self.resolve_constant(root)
+            .map(|kind| kind == ConstKind::Universal)
+    }
+
+    /// Returns `true` if `root` is the Universal set, see [`root_constant`](Self::root_constant).
+    pub fn is_tautology(&self, root: NodeId) -> bool {
+        self.root_constant(root) == Some(true)
+    }
+
+    /// Returns `true` if `root` is the Empty set, see [`root_constant`](Self::root_constant).
+    pub fn is_contradiction(&self, root: NodeId) -> bool {
+        self.root_constant(root) == Some(false)
+    }
+
+    /// Counts how many roots are always true, always false, or conditional.
+    ///
+    /// Only roots that are exactly `UNIVERSAL` or `EMPTY` count as constant, so this is
+    /// most useful after [`optimize`](Self::optimize) has folded what it can.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{Expression, NodeId, expr::RootSummary};
+    ///
+    /// let mut expr = Expression::new();
+    /// let a = expr.set("A");
+    /// expr.add_root(a);
+    /// expr.add_root(NodeId::UNIVERSAL);
+    /// expr.add_root(NodeId::EMPTY);
+    /// expr.add_root(NodeId::EMPTY);
+    ///
+    /// let summary = expr.root_constant_summary();
+    /// assert_eq!(
+    ///     summary,
+    ///     RootSummary { always_true: 1, always_false: 2, conditional: 1 }
+    /// );
+    /// ```
+    pub fn root_constant_summary(&self) -> RootSummary {
+        let mut summary = RootSummary::default();
+        for &root in &self.roots {
+            match self.root_constant(root) {
+                Some(true) => summary.always_true += 1,
+                Some(false) => summary.always_false += 1,
+                None => summary.conditional += 1,
+            }
+        }
+        summary
+    }
+}
+
+impl<T: Clone + Hash + Eq, Meta> Expression<T, Meta> {
+    /// Finds the terms that must be present (or absent) for `root` to be true.
+    ///
+    /// Every combination of `terms` is evaluated with a [`BoolEval`], so the cost is
+    /// `2^terms.len()` evaluations and this is only meant for short term lists. Terms not listed
+    /// are treated as absent. Returns `None` if no combination makes the root true.
+    ///
+    /// # Panics
+    /// Panics if more than 32 terms are given.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{ExpressionBuilder, expr::NecessaryTerms};
+    ///
+    /// let builder = ExpressionBuilder::new();
+    /// let (admin, editor, banned) = (builder.leaf("Admin"), builder.leaf("Editor"), builder.leaf("Banned"));
+    /// builder.add_root(admin & (editor | !editor) & !banned);
+    /// let expr = builder.build();
+    /// let root = expr.roots().next().unwrap();
+    ///
+    /// let support = expr.necessary_terms(*root, &["Admin", "Editor", "Banned"]);
+    /// assert_eq!(
+    ///     support,
+    ///     Some(NecessaryTerms { present: vec!["Admin"], absent: vec!["Banned"] })
+    /// );
+    /// ```
+    pub fn necessary_terms(&self, root: NodeId, terms: &[T]) -> Option<NecessaryTerms<T>> {
+        assert!(terms.len() <= 32, "too many terms to enumerate");
+
+        // bit i set = term i is set in every (or no) satisfying assignment so far
+        let all = (1u64 << terms.len()) - 1;
+        let mut always = all;
+        let mut never = all;
+        let mut satisfiable = false;
+
+        let roots = [root];
+        let mut store = vec![None; self.nodes.len() * 2];
+        let mut active = Vec::new();
+        let mut include_indices = Vec::new();
+        let mut exclude_indices = Vec::new();
+        for mask in 0..=all {
+            let mut solver = BoolEval::new();
+            for (i, term) in terms.iter().enumerate() {
+                if mask & (1 << i) != 0 {
+                    solver.add(term.clone());
+                }
+            }
+
+            // BoolEval never fails
+            store.fill(None);
+            let result = Self::fill_store(
+                self.nodes.as_slice(),
+                &roots,
+                &mut solver,
+                &mut store,
+                &mut active,
+                &mut include_indices,
+                &mut exclude_indices,
+            )
+            .and_then(|_| Self::collect_roots(&roots, &mut solver, &mut store));
+            if result == Ok(vec![true]) {
+                satisfiable = true;
+                always &= mask;
+                never &= !mask;
+            }
+        }
+
+        if !satisfiable {
+            return None;
+        }
+        let pick = |bits: u64| {
+            terms
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| bits & (1 << i) != 0)
+                .map(|(_, term)| term.clone())
+                .collect()
+        };
+        Some(NecessaryTerms {
+            present: pick(always),
+            absent: pick(never),
+        })
+    }
+
+    /// Counts the assignments of `vars` that make each root true, in root order.
+    ///
+    /// Each of the `2^vars.len()` assignments sets some of `vars` present and the rest absent;
+    /// terms not listed in `vars` are always absent. Listed terms the expression never uses
+    /// are free, so each one doubles every count without being enumerated. The terms that
+    /// are used are enumerated with a [`BoolEval`], so the cost is `2^used` evaluations.
+    /// `vars` should not contain duplicates.
+    ///
+    /// # Panics
+    /// Panics if more than 32 of `vars` are used by the expression, or if `vars` has 64 or
+    /// more entries.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::ExpressionBuilder;
+    ///
+    /// let builder = ExpressionBuilder::new();
+    /// let (admin, editor) = (builder.leaf("Admin"), builder.leaf("Editor"));
+    /// builder.add_root(admin & editor);
+    /// builder.add_root(admin | editor);
+    /// let expr = builder.build();
+    ///
+    /// assert_eq!(expr.count_models(&["Admin", "Editor"]), vec![1, 3]);
+    /// // "Guest" isn't used, so it doubles everything
+    /// assert_eq!(expr.count_models(&["Admin", "Editor", "Guest"]), vec![2, 6]);
+    /// ```
+    pub fn count_models(&self, vars: &[T]) -> Vec<u64> {
+        assert!(vars.len() < 64, "too many variables to count");
+        let used: Vec<&T> = self.terms().collect();
+        let bound: Vec<&T> = vars.iter().filter(|var| used.contains(var)).collect();
+        assert!(bound.len() <= 32, "too many terms to enumerate");
+        let free = vars.len() - bound.len();
+
+        let mut counts = vec![0; self.roots.len()];
+        let mut store = vec![None; self.nodes.len() * 2];
+        let mut active = Vec::new();
+        let mut include_indices = Vec::new();
+        let mut exclude_indices = Vec::new();
+        for mask in 0..(1u64 << bound.len()) {
+            let mut solver = BoolEval::new();
+            for (i, &term) in bound.iter().enumerate() {
+                if mask & (1 << i) != 0 {
+                    solver.add(term.clone());
+                }
+            }
+
+            // BoolEval never fails
+            store.fill(None);
+            let result = Self::fill_store(
+                self.nodes.as_slice(),
+                &self.roots,
+                &mut solver,
+                &mut store,
+                &mut active,
+                &mut include_indices,
+                &mut exclude_indices,
+            )
+            .and_then(|_| Self::collect_roots(&self.roots, &mut solver, &mut store));
+            for (count, value) in counts.iter_mut().zip(result.unwrap_or_default()) {
+                *count += value as u64;
+            }
+        }
+
+        for count in &mut counts {
+            *count <<= free;
+        }
+        counts
+    }
+}
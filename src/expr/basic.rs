@@ -1,12 +1,11 @@
-use std::{
-    fmt::Display,
-    hash::{BuildHasher, Hash},
-    slice::Iter,
-};
+use std::{borrow::Cow, fmt::Display, hash::Hash, slice::Iter};
 
-use hashbrown::hash_map::RawEntryMut;
+use hashbrown::{HashMap, hash_map::RawEntryMut};
 
-use crate::expr::{Expression, Node, NodeId, iter::ExpressionDependencyIter};
+use crate::expr::{
+    ConstKind, ExprError, Expression, InternState, Node, NodeId, build_cache, generate_uuid,
+    iter::ExpressionDependencyIter,
+};
 
 impl<T> Expression<T> {
     /// Creates a new, empty Expression.
@@ -14,10 +13,94 @@ impl<T> Expression<T> {
         Self::default()
     }
 
+    /// Wraps already-canonical nodes and roots without hashing or deduplicating anything.
+    ///
+    /// This is the fast path for reloading expressions that this crate produced, such as nodes
+    /// read back from your own storage. The dedup index is not built up front; it is built the
+    /// first time a node is added, so expressions that are only evaluated never pay for it.
+    ///
+    /// Nothing is checked. The caller guarantees that:
+    /// * `nodes[0]` is [`Node::Empty`] and no other node is.
+    /// * Every child ID points to a node with a lower index than its parent.
+    /// * No two nodes are equal.
+    /// * Every root points into `nodes`.
+    ///
+    /// Breaking these is not undefined behaviour, but it can cause panics, wrong results, or
+    /// duplicated nodes later on. For untrusted input, deserialize through `serde` instead.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{Expression, ExpressionBuilder};
+    ///
+    /// let builder = ExpressionBuilder::<&str>::new();
+    /// builder.add_root(builder.leaf("A") & !builder.leaf("B"));
+    /// let expr = builder.build();
+    ///
+    /// let nodes: Vec<_> = expr.nodes().cloned().collect();
+    /// let roots = expr.roots_owned();
+    /// let mut loaded = Expression::from_nodes_unchecked(nodes, roots);
+    /// let root = loaded.roots().next().unwrap();
+    /// assert_eq!(loaded.to_string(root), "([A] & [B]')");
+    ///
+    /// // adding nodes still deduplicates
+    /// let count = loaded.node_count();
+    /// loaded.set("A");
+    /// assert_eq!(loaded.node_count(), count);
+    /// ```
+    pub fn from_nodes_unchecked(nodes: Vec<Node<T>>, roots: Vec<NodeId>) -> Self {
+        Self {
+            nodes,
+            meta: vec![(); roots.len()],
+            roots,
+            ..Self::default()
+        }
+    }
+}
+
+impl<T, Meta> Expression<T, Meta> {
+    /// Creates a new, empty Expression that interns nodes with rapidhash's fast variant.
+    ///
+    /// Every added node is hashed for deduplication, which dominates build time for
+    /// leaf-heavy expressions. The fast variant is cheaper, notably for integer terms, but its
+    /// weaker mixing leaves the table open to collision flooding, so only use it for trusted
+    /// terms. The choice belongs to this expression alone: clones and the expressions that
+    /// pruning or rebuilding return keep it, while deserialized expressions start on the
+    /// default quality variant.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{Expression, ExpressionBuilder};
+    ///
+    /// let mut expr = Expression::<u32>::with_fast_hash();
+    /// let a = expr.set(1);
+    /// assert_eq!(expr.set(1), a); // still deduplicated
+    ///
+    /// // builders can compile straight into it
+    /// let builder = ExpressionBuilder::new();
+    /// builder.add_root(builder.leaf(2u32) | builder.leaf(3u32));
+    /// builder.build_into(&mut expr);
+    /// assert_eq!(expr.root_count(), 1);
+    /// ```
+    pub fn with_fast_hash() -> Self {
+        Self {
+            cache: HashMap::with_hasher(InternState::fast()),
+            ..Self::default()
+        }
+    }
+
+    // an empty expression that interns the same way as this one
+    pub(crate) fn empty_like<U, M>(&self) -> Expression<U, M> {
+        Expression {
+            cache: HashMap::with_hasher(self.cache.hasher().reseeded()),
+            ..Expression::default()
+        }
+    }
+
     /// registers a node as a "Root" of the expression.
     ///
     /// Roots are the entry points for evaluation and dependency iteration.
-    /// Nodes not reachable from a root are considered dead code.
+    /// Nodes not reachable from a root are considered dead code. The root gets the default
+    /// metadata; see [`add_root_with_meta`](Self::add_root_with_meta).
     ///
     /// # Panics
     /// Panics if `root` is not a valid ID belonging to this expression.
@@ -28,7 +111,31 @@ impl<T> Expression<T> {
     /// let a = expr.set("A");
     /// expr.add_root(a);
     /// ```
-    pub fn add_root(&mut self, root: NodeId) {
+    pub fn add_root(&mut self, root: NodeId)
+    where
+        Meta: Default,
+    {
+        self.add_root_with_meta(root, Meta::default());
+    }
+
+    /// Registers a node as a root, attaching `meta` to it.
+    ///
+    /// # Panics
+    /// Panics if `root` is not a valid ID belonging to this expression.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::Expression;
+    ///
+    /// let mut expr: Expression<&str, u32> = Expression::default();
+    /// let a = expr.set("A");
+    /// let b = expr.set("B");
+    /// expr.add_root_with_meta(a, 42);
+    /// expr.add_root_with_meta(b, 7);
+    ///
+    /// assert_eq!(expr.root_meta(1), &7);
+    /// ```
+    pub fn add_root_with_meta(&mut self, root: NodeId, meta: Meta) {
         if root.idx() >= self.nodes.len() {
             panic!(
                 "Invalid NodeId: ID {} for node {} does not exist in this expression. The expression has {} nodes.",
@@ -38,6 +145,53 @@ impl<T> Expression<T> {
             );
         }
         self.roots.push(root);
+        self.meta.push(meta);
+    }
+
+    /// Returns the metadata attached to the root at `root_idx`.
+    ///
+    /// # Panics
+    /// Panics if `root_idx` is out of range.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{Expression, ExpressionBuilder, opt::OptimizerConfig};
+    ///
+    /// struct Rule {
+    ///     id: u32,
+    ///     priority: u8,
+    /// }
+    ///
+    /// let mut expr: Expression<&str, Option<Rule>> = Expression::default();
+    /// let builder = ExpressionBuilder::new();
+    /// let (a, b) = (builder.leaf("A"), builder.leaf("B"));
+    /// builder.add_root(a & (a | b));
+    /// builder.add_root(b);
+    /// builder.build_into(&mut expr);
+    /// *expr.root_meta_mut(0) = Some(Rule { id: 42, priority: 1 });
+    ///
+    /// // the metadata follows its root through optimization and pruning
+    /// expr.optimize(&mut OptimizerConfig::default());
+    /// let expr = expr.prune::<()>();
+    /// assert_eq!(expr.to_string(&expr.roots_owned()[0]), "[A]");
+    /// assert_eq!(expr.root_meta(0).as_ref().map(|r| (r.id, r.priority)), Some((42, 1)));
+    /// assert!(expr.root_meta(1).is_none());
+    /// ```
+    pub fn root_meta(&self, root_idx: usize) -> &Meta {
+        &self.meta[root_idx]
+    }
+
+    /// Returns a mutable reference to the metadata of the root at `root_idx`.
+    ///
+    /// # Panics
+    /// Panics if `root_idx` is out of range.
+    pub fn root_meta_mut(&mut self, root_idx: usize) -> &mut Meta {
+        &mut self.meta[root_idx]
+    }
+
+    /// Returns the metadata of every root, aligned with [`roots`](Self::roots).
+    pub fn root_metas(&self) -> &[Meta] {
+        &self.meta
     }
 
     /// A helper to build logic and add it as a root in one closure.
@@ -56,16 +210,224 @@ impl<T> Expression<T> {
     ///     e.intersection([a, b])
     /// });
     /// ```
-    pub fn build_root(&mut self, root: impl FnOnce(&mut Self) -> NodeId) {
+    pub fn build_root(&mut self, root: impl FnOnce(&mut Self) -> NodeId)
+    where
+        Meta: Default,
+    {
         let root = root(self);
         self.add_root(root);
     }
 
+    /// Replaces every root with the result of passing it through a closure.
+    ///
+    /// The closure may allocate new nodes in the expression, making this the batch version
+    /// of [`build_root`](Self::build_root) for existing roots. Each root keeps its metadata.
+    /// The UUID is regenerated, so any attached `EvaluatorCache` resets on its next use.
+    ///
+    /// # Panics
+    /// Panics if the closure returns an ID that does not belong to this expression.
+    ///
+    /// # Example
+    /// ```rust
+    /// let mut expr = logify::Expression::new();
+    /// let a = expr.set("A");
+    /// let b = expr.set("B");
+    /// expr.add_root(a);
+    /// expr.add_root(b);
+    ///
+    /// // Apply a kill switch to every rule: root & !Kill
+    /// let kill = expr.set("Kill");
+    /// expr.rewrite_roots(|e, root| {
+    ///     let not_kill = e.complement(kill);
+    ///     e.intersection([root, not_kill])
+    /// });
+    ///
+    /// let root = expr.roots().next().unwrap();
+    /// assert_eq!(expr.to_string(root), "([A] & [Kill]')");
+    /// ```
+    pub fn rewrite_roots<F: FnMut(&mut Self, NodeId) -> NodeId>(&mut self, mut f: F) {
+        let roots = std::mem::take(&mut self.roots);
+        let meta = std::mem::take(&mut self.meta);
+        for (root, meta) in roots.into_iter().zip(meta) {
+            let root = f(self, root);
+            self.add_root_with_meta(root, meta);
+        }
+        self.uuid = generate_uuid();
+    }
+
+    /// Replaces the root at `root_idx` with its complement.
+    ///
+    /// Negation is a flag on the ID, so no nodes are allocated. The other roots and the
+    /// root's metadata are untouched. The UUID is regenerated, so any attached
+    /// `EvaluatorCache` resets on its next use.
+    ///
+    /// # Panics
+    /// Panics if `root_idx` is out of range.
+    ///
+    /// # Example
+    /// ```rust
+    /// let mut expr = logify::Expression::new();
+    /// let view = expr.set("View");
+    /// let edit = expr.set("Edit");
+    /// expr.add_root(view);
+    /// expr.add_root(edit);
+    ///
+    /// expr.negate_root(0);
+    /// let roots = expr.roots_owned();
+    /// assert_eq!(expr.to_string(&roots[0]), "[View]'");
+    /// assert_eq!(expr.to_string(&roots[1]), "[Edit]");
+    /// ```
+    pub fn negate_root(&mut self, root_idx: usize) {
+        if root_idx >= self.roots.len() {
+            panic!(
+                "Invalid root index: root {} does not exist in this expression. The expression has {} roots.",
+                root_idx,
+                self.roots.len(),
+            );
+        }
+        self.roots[root_idx] = self.roots[root_idx].not();
+        self.uuid = generate_uuid();
+    }
+
+    /// Removes the root at `index`, returning its ID.
+    ///
+    /// Later roots shift down by one, and the root's metadata is dropped with it. Nodes only
+    /// reachable from the removed root are left in place as dead nodes; call
+    /// [`prune`](Self::prune) to reclaim them. The UUID is regenerated, so any attached
+    /// `EvaluatorCache` resets on its next use.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of range.
+    ///
+    /// # Example
+    /// ```rust
+    /// let mut expr = logify::Expression::new();
+    /// let a = expr.set("A");
+    /// let b = expr.set("B");
+    /// expr.add_root(a);
+    /// expr.add_root(b);
+    ///
+    /// assert_eq!(expr.remove_root(0), a);
+    /// assert_eq!(expr.roots_owned(), [b]);
+    /// ```
+    pub fn remove_root(&mut self, index: usize) -> NodeId {
+        if index >= self.roots.len() {
+            panic!(
+                "Invalid root index: root {} does not exist in this expression. The expression has {} roots.",
+                index,
+                self.roots.len(),
+            );
+        }
+        self.meta.remove(index);
+        self.uuid = generate_uuid();
+        self.roots.remove(index)
+    }
+
+    /// Reorders the roots, placing the root at index `perm[i]` at index `i`.
+    ///
+    /// Each root's metadata moves with it. Evaluation results are positional, so the UUID is
+    /// regenerated, and any attached `EvaluatorCache` resets on its next use.
+    ///
+    /// # Panics
+    /// Panics if `perm` is not a permutation of `0..root_count()`.
+    ///
+    /// # Example
+    /// ```rust
+    /// let mut expr = logify::Expression::new();
+    /// let delete = expr.set("Delete");
+    /// let view = expr.set("View");
+    /// let edit = expr.set("Edit");
+    /// expr.add_root(delete);
+    /// expr.add_root(view);
+    /// expr.add_root(edit);
+    ///
+    /// expr.reorder_roots(&[1, 2, 0]);
+    /// assert_eq!(expr.roots_owned(), [view, edit, delete]);
+    /// ```
+    pub fn reorder_roots(&mut self, perm: &[usize]) {
+        let mut seen = vec![false; self.roots.len()];
+        let is_perm = perm.len() == self.roots.len()
+            && perm
+                .iter()
+                .all(|&i| i < seen.len() && !std::mem::replace(&mut seen[i], true));
+        if !is_perm {
+            panic!(
+                "Invalid root permutation: {:?} is not a permutation of the {} roots.",
+                perm,
+                self.roots.len(),
+            );
+        }
+
+        let mut meta: Vec<Option<Meta>> = std::mem::take(&mut self.meta)
+            .into_iter()
+            .map(Some)
+            .collect();
+        self.roots = perm.iter().map(|&i| self.roots[i]).collect();
+        self.meta = perm.iter().map(|&i| meta[i].take().unwrap()).collect();
+        self.uuid = generate_uuid();
+    }
+
     /// Iterate over the registered root IDs.
+    ///
+    /// # Root Order
+    /// Roots are yielded in the order they were added. Transformations such as
+    /// [`optimize`](Self::optimize), [`prune`](Self::prune), [`compress`](Self::compress), and
+    /// absorbing via [`Extend`] only remap the IDs in place: they never reorder or drop roots,
+    /// even ones that collapse to a constant. Positional indexing like `results[0]` stays valid.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{Expression, ExpressionBuilder, opt::OptimizerConfig};
+    ///
+    /// let builder = ExpressionBuilder::<&str>::new();
+    /// let (a, b) = (builder.leaf("A"), builder.leaf("B"));
+    /// builder.add_root(a & b);
+    /// builder.add_root(a & !a); // collapses to Empty
+    /// builder.add_root(a | b);
+    /// let mut expr = builder.build();
+    ///
+    /// let order = |e: &Expression<&str>| e.roots().map(|r| e.to_string(r)).collect::<Vec<_>>();
+    /// let expected = ["([A] & [B])", "EMPTY", "([A] | [B])"];
+    ///
+    /// expr.optimize(&mut OptimizerConfig::default());
+    /// assert_eq!(order(&expr), expected);
+    /// let expr = expr.prune::<()>();
+    /// assert_eq!(order(&expr), expected);
+    /// let expr = expr.compress::<()>(None);
+    /// assert_eq!(order(&expr), expected);
+    ///
+    /// let mut combined = Expression::new();
+    /// combined.extend(expr);
+    /// assert_eq!(order(&combined), expected);
+    /// ```
     pub fn roots(&self) -> Iter<'_, NodeId> {
         self.roots.iter()
     }
 
+    /// Returns a copy of the roots, in order.
+    ///
+    /// Unlike [`roots`](Self::roots), the result does not borrow the expression, so it can be
+    /// iterated while calling `&mut self` methods.
+    ///
+    /// # Example
+    /// ```rust
+    /// let mut expr = logify::Expression::new();
+    /// let a = expr.set("A");
+    /// let b = expr.set("B");
+    /// expr.add_root(a);
+    /// expr.add_root(b);
+    ///
+    /// let guard = expr.set("Guard");
+    /// for root in expr.roots_owned() {
+    ///     let guarded = expr.intersection([root, guard]);
+    ///     expr.add_root(guarded);
+    /// }
+    /// assert_eq!(expr.root_count(), 4);
+    /// ```
+    pub fn roots_owned(&self) -> Vec<NodeId> {
+        self.roots.clone()
+    }
+
     /// Returns the number of roots.
     pub fn root_count(&self) -> usize {
         self.roots.len()
@@ -95,14 +457,75 @@ impl<T> Expression<T> {
     pub fn iter_dependencies(&self) -> ExpressionDependencyIter<'_, T> {
         ExpressionDependencyIter::new(self)
     }
+
+    /// Checks whether an ID refers to a constant.
+    ///
+    /// There is no `Node::Universal`: the Universal set is the negation of the
+    /// [`Node::Empty`] node, so matching on a `Node` alone can't tell the two apart. This
+    /// combines the node with the ID's sign.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{Expression, NodeId, expr::ConstKind};
+    ///
+    /// let mut expr = Expression::new();
+    /// let a = expr.set("A");
+    ///
+    /// assert_eq!(expr.resolve_constant(NodeId::EMPTY), Some(ConstKind::Empty));
+    /// assert_eq!(expr.resolve_constant(NodeId::UNIVERSAL), Some(ConstKind::Universal));
+    /// assert_eq!(expr.resolve_constant(a), None);
+    /// ```
+    pub fn resolve_constant(&self, id: NodeId) -> Option<ConstKind> {
+        match self.nodes[id.idx()] {
+            Node::Empty if id.is_neg() => Some(ConstKind::Universal),
+            Node::Empty => Some(ConstKind::Empty),
+            _ => None,
+        }
+    }
+
+    /// Returns the immediate children of a node.
+    ///
+    /// Leaves and the Empty node have no children. The negation flag of `id` is ignored, so
+    /// `(A | B)'` yields `A` and `B`.
+    ///
+    /// # Example
+    /// ```rust
+    /// let mut expr = logify::Expression::new();
+    /// let a = expr.set("A");
+    /// let b = expr.set("B");
+    /// let ab = expr.union([a, b]);
+    ///
+    /// assert_eq!(expr.direct_children(ab), &[a, b]);
+    /// assert!(expr.direct_children(a).is_empty());
+    /// ```
+    pub fn direct_children(&self, id: NodeId) -> &[NodeId] {
+        match &self.nodes[id.idx()] {
+            Node::Union(kids) | Node::Intersection(kids) => kids,
+            _ => &[],
+        }
+    }
+
+    /// Iterates the immediate children of a node, along with the node each one points to.
+    ///
+    /// See [`direct_children`](Self::direct_children).
+    pub fn direct_children_resolved(&self, id: NodeId) -> impl Iterator<Item = (NodeId, &Node<T>)> {
+        self.direct_children(id)
+            .iter()
+            .map(|&kid| (kid, &self.nodes[kid.idx()]))
+    }
 }
 
-impl<T: Hash + PartialEq> Expression<T> {
+impl<T: Hash + PartialEq, Meta> Expression<T, Meta> {
     pub(crate) fn alloc(&mut self, node: Node<T>) -> NodeId {
         if let Node::Empty = node {
             return NodeId::EMPTY;
         }
 
+        // built lazily for expressions made by `from_nodes_unchecked`
+        if self.cache.is_empty() && self.nodes.len() > 1 {
+            self.cache = build_cache(&self.nodes, *self.cache.hasher());
+        }
+
         let hasher_builder = *self.cache.hasher();
         let hash = hasher_builder.hash_one(&node);
 
@@ -175,24 +598,19 @@ impl<T: Hash + PartialEq> Expression<T> {
     /// assert_eq!(a_or_a, a);
     /// ```
     pub fn union(&mut self, children: impl IntoIterator<Item = NodeId>) -> NodeId {
-        let mut children: Vec<NodeId> = children.into_iter().collect();
+        // identity, E | A == A, dropped while collecting to avoid shifting later
+        let mut children: Vec<NodeId> = children
+            .into_iter()
+            .filter(|&c| c != NodeId::EMPTY)
+            .collect();
 
         // places A and !A next to each other
         children.sort_unstable(); // commutative, B | A == A | B
         children.dedup(); // idempotent, A | A == A
 
-        // identity and annulment
-        // remove Empty (E | A == A) and test for Universal (U | A == U)
-        if let Some(&first) = children.first() {
-            if first == NodeId::UNIVERSAL {
-                return NodeId::UNIVERSAL;
-            }
-            if first == NodeId::EMPTY {
-                if children.get(1) == Some(&NodeId::UNIVERSAL) {
-                    return NodeId::UNIVERSAL;
-                }
-                children.remove(0); // TODO: O(N) SHIFT!!
-            }
+        // annulment, U | A == U (sorts first)
+        if children.first() == Some(&NodeId::UNIVERSAL) {
+            return NodeId::UNIVERSAL;
         }
 
         // universality, A | !A == U
@@ -237,21 +655,19 @@ impl<T: Hash + PartialEq> Expression<T> {
     /// assert_eq!(impossible, logify::NodeId::EMPTY);
     /// ```
     pub fn intersection(&mut self, children: impl IntoIterator<Item = NodeId>) -> NodeId {
-        let mut children: Vec<NodeId> = children.into_iter().collect();
+        // identity, U & A == A, dropped while collecting to avoid shifting later
+        let mut children: Vec<NodeId> = children
+            .into_iter()
+            .filter(|&c| c != NodeId::UNIVERSAL)
+            .collect();
 
         // places A and !A next to each other
         children.sort_unstable(); // commutative, B & A == A & B
         children.dedup(); // idempotent, A & A == A
 
-        // identity and annulment
-        // remove Universal (U & A == A) and test for Empty (E & A == E)
-        if let Some(&first) = children.first() {
-            if first == NodeId::EMPTY {
-                return NodeId::EMPTY;
-            }
-            if first == NodeId::UNIVERSAL {
-                children.remove(0);
-            }
+        // annulment, E & A == E (sorts first)
+        if children.first() == Some(&NodeId::EMPTY) {
+            return NodeId::EMPTY;
         }
 
         // annihilation, A & !A == E
@@ -271,14 +687,186 @@ impl<T: Hash + PartialEq> Expression<T> {
         self.alloc(Node::Intersection(children))
     }
 
+    /// Creates a logical Exclusive Or (`A XOR B`).
+    ///
+    /// There is no dedicated XOR node. The result is the canonical decomposition
+    /// `(A & !B) | (!A & B)`, built through the smart constructors so it deduplicates like any
+    /// other Union. Operands are normalized first, so `xor(a, b)`, `xor(b, a)`, and
+    /// `xor(!a, !b)` share one node, and `xor(!a, b)` is its negation.
+    ///
+    /// # Simplifications Performed
+    /// * **Self-Inverse:** `A ^ A` -> `Empty`.
+    /// * **Complements:** `A ^ !A` -> `Universal`.
+    /// * **Identity:** `A ^ Empty` -> `A`.
+    /// * **Negation:** `A ^ Universal` -> `!A`.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use logify::{Expression, NodeId};
+    /// let mut expr = Expression::new();
+    /// let a = expr.set("A");
+    /// let b = expr.set("B");
+    ///
+    /// let a_xor_b = expr.xor(a, b);
+    /// assert_eq!(expr.to_string(&a_xor_b), "(([A] & [B]') | ([A]' & [B]))");
+    /// assert_eq!(expr.xor(b, a), a_xor_b);
+    ///
+    /// // Negating one side negates the result
+    /// let not_b = expr.complement(b);
+    /// assert_eq!(expr.xor(a, not_b), expr.complement(a_xor_b));
+    ///
+    /// assert_eq!(expr.xor(a, a), NodeId::EMPTY);
+    /// let not_a = expr.complement(a);
+    /// assert_eq!(expr.xor(a, not_a), NodeId::UNIVERSAL);
+    /// ```
+    pub fn xor(&mut self, a: NodeId, b: NodeId) -> NodeId {
+        // !A ^ B == !(A ^ B), so strip the signs and apply them to the result
+        let neg = a.is_neg() != b.is_neg();
+        let (mut a, mut b) = (
+            NodeId::new(a.idx() as u32, false),
+            NodeId::new(b.idx() as u32, false),
+        );
+        if a > b {
+            std::mem::swap(&mut a, &mut b); // commutative, B ^ A == A ^ B
+        }
+
+        let result = if a == b {
+            NodeId::EMPTY // A ^ A == E
+        } else if a == NodeId::EMPTY {
+            b // E ^ B == B
+        } else {
+            let left = self.intersection([a, b.not()]);
+            let right = self.intersection([a.not(), b]);
+            self.union([left, right])
+        };
+        if neg { result.not() } else { result }
+    }
+
+    /// Creates a multiplexer (`IF C THEN T ELSE E`), stored as `(C & T) | (!C & E)`.
+    ///
+    /// The branches are built with the smart constructors, so they deduplicate normally.
+    ///
+    /// # Simplifications Performed
+    /// * **Same Branches:** `if C then T else T` -> `T`.
+    /// * **Constant Condition:** `if Universal then T else E` -> `T`, and `if Empty ...` -> `E`.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use logify::{Expression, NodeId};
+    /// let mut expr = Expression::new();
+    /// let eu = expr.set("EU");
+    /// let gdpr = expr.set("GdprConsent");
+    /// let basic = expr.set("BasicConsent");
+    ///
+    /// let allowed = expr.if_then_else(eu, gdpr, basic);
+    /// assert_eq!(expr.to_string(&allowed), "(([EU] & [GdprConsent]) | ([EU]' & [BasicConsent]))");
+    ///
+    /// assert_eq!(expr.if_then_else(eu, gdpr, gdpr), gdpr);
+    /// assert_eq!(expr.if_then_else(NodeId::UNIVERSAL, gdpr, basic), gdpr);
+    /// ```
+    pub fn if_then_else(&mut self, cond: NodeId, then: NodeId, els: NodeId) -> NodeId {
+        if then == els {
+            return then;
+        }
+        let on_true = self.intersection([cond, then]);
+        let on_false = self.intersection([cond.not(), els]);
+        self.union([on_true, on_false])
+    }
+
     /// Returns the complement A => A'.
     pub fn complement(&self, child: NodeId) -> NodeId {
         child.not()
     }
+
+    /// Adds a global condition to every root, turning each into `root & term` (or
+    /// `root & !term` if `negated`).
+    ///
+    /// The leaf is created once and shared by every root. Roots are rebuilt with the smart
+    /// constructors, so a root that already contains the condition stays unchanged, and the
+    /// UUID is regenerated as with [`rewrite_roots`](Self::rewrite_roots).
+    ///
+    /// # Example
+    /// ```rust
+    /// let mut expr = logify::Expression::new();
+    /// let a = expr.set("A");
+    /// let b = expr.set("B");
+    /// expr.add_root(a);
+    /// expr.add_root(b);
+    ///
+    /// expr.constrain_all_roots("GlobalBlock", true);
+    ///
+    /// let roots: Vec<_> = expr.roots().map(|r| expr.to_string(r)).collect();
+    /// assert_eq!(roots, ["([A] & [GlobalBlock]')", "([B] & [GlobalBlock]')"]);
+    /// ```
+    pub fn constrain_all_roots(&mut self, term: T, negated: bool) {
+        let leaf = self.set(term);
+        let leaf = if negated { self.complement(leaf) } else { leaf };
+        self.rewrite_roots(|e, root| e.intersection([root, leaf]));
+    }
+
+    /// Edits the children of a Union or Intersection through a closure.
+    ///
+    /// Nodes are interned and never change in place, so the edited children are passed back
+    /// through the smart constructor ([`union`](Self::union) or
+    /// [`intersection`](Self::intersection)), which re-sorts and simplifies them. The resulting
+    /// ID is returned, negated if `id` was. Existing parents and roots still point to the old
+    /// node; repoint them with [`rewrite_roots`](Self::rewrite_roots) as needed. The UUID is
+    /// regenerated.
+    ///
+    /// # Errors
+    /// * [`ExprError::InvalidNode`] if `id` or one of the new children does not exist.
+    /// * [`ExprError::NotAGroup`] if `id` is a leaf or a constant.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{Expression, expr::ExprError};
+    ///
+    /// let mut expr = Expression::new();
+    /// let a = expr.set("A");
+    /// let b = expr.set("B");
+    /// let c = expr.set("C");
+    /// let ab = expr.union([a, b]);
+    ///
+    /// let abc = expr.modify_group(ab, |kids| kids.push(c)).unwrap();
+    /// assert_eq!(expr.to_string(&abc), "([A] | [B] | [C])");
+    ///
+    /// // Removing all but one child collapses the group
+    /// let only_a = expr.modify_group(ab, |kids| kids.retain(|&k| k == a)).unwrap();
+    /// assert_eq!(only_a, a);
+    ///
+    /// assert_eq!(expr.modify_group(a, |_| {}), Err(ExprError::NotAGroup));
+    /// ```
+    pub fn modify_group<F: FnOnce(&mut Vec<NodeId>)>(
+        &mut self,
+        id: NodeId,
+        f: F,
+    ) -> Result<NodeId, ExprError> {
+        let (mut kids, is_union) = match self.nodes.get(id.idx()) {
+            Some(Node::Union(kids)) => (kids.clone(), true),
+            Some(Node::Intersection(kids)) => (kids.clone(), false),
+            Some(_) => return Err(ExprError::NotAGroup),
+            None => return Err(ExprError::InvalidNode),
+        };
+
+        f(&mut kids);
+        if kids.iter().any(|k| k.idx() >= self.nodes.len()) {
+            return Err(ExprError::InvalidNode);
+        }
+
+        let new_id = if is_union {
+            self.union(kids)
+        } else {
+            self.intersection(kids)
+        };
+        self.uuid = generate_uuid();
+        Ok(if id.is_neg() { new_id.not() } else { new_id })
+    }
 }
 
-impl<T: Display> Expression<T> {
-    /// Recursively formats the expression starting from the given root.
+impl<T: Display, Meta> Expression<T, Meta> {
+    /// Formats the expression starting from the given root.
+    ///
+    /// Uses an explicit stack, so it is safe for very deep graphs (no stack overflow).
     ///
     /// # Example
     /// ```rust
@@ -291,24 +879,211 @@ impl<T: Display> Expression<T> {
     /// assert_eq!(expr.to_string(&root), "([A] & [B])");
     /// ```
     pub fn to_string(&self, root: &NodeId) -> String {
-        let is_neg = if root.is_neg() { "'" } else { "" };
+        // nodes still to write, and closing text to write once their children are done
+        enum Pending<'a> {
+            Node(NodeId),
+            Text(&'a str),
+        }
+
+        let mut out = String::new();
+        let mut stack = vec![Pending::Node(*root)];
+        while let Some(pending) = stack.pop() {
+            let id = match pending {
+                Pending::Node(id) => id,
+                Pending::Text(text) => {
+                    out.push_str(text);
+                    continue;
+                }
+            };
+            let is_neg = if id.is_neg() { "'" } else { "" };
+            let (children, op) = match &self.nodes[id.idx()] {
+                Node::Set(set) => {
+                    out.push_str(&format!("[{}]{}", set, is_neg));
+                    continue;
+                }
+                Node::Empty if id.is_neg() => {
+                    out.push_str("UNIVERSAL");
+                    continue;
+                }
+                Node::Empty => {
+                    out.push_str("EMPTY");
+                    continue;
+                }
+                Node::Union(children) => (children, " | "),
+                Node::Intersection(children) => (children, " & "),
+            };
+
+            // pushed in reverse, so they pop in reading order
+            out.push('(');
+            stack.push(Pending::Text(if id.is_neg() { ")'" } else { ")" }));
+            for (i, &kid) in children.iter().enumerate().rev() {
+                stack.push(Pending::Node(kid));
+                if i > 0 {
+                    stack.push(Pending::Text(op));
+                }
+            }
+        }
+        out
+    }
+
+    /// Formats the root, borrowing instead of allocating when it is a single positive leaf.
+    ///
+    /// For high-volume logging of simple filters. A positive `Set` root is returned as the bare
+    /// term, borrowed from the expression. Anything else falls back to an owned
+    /// [`to_string`](Self::to_string).
+    ///
+    /// # Example
+    /// ```rust
+    /// # use std::borrow::Cow;
+    /// # use logify::Expression;
+    /// let mut expr = Expression::new();
+    /// let a = expr.set("A");
+    /// let b = expr.set("B");
+    /// let root = expr.union([a, b]);
+    ///
+    /// assert!(matches!(expr.display_cow(&a), Cow::Borrowed("A")));
+    /// assert_eq!(expr.display_cow(&root), "([A] | [B])");
+    /// ```
+    pub fn display_cow(&self, root: &NodeId) -> Cow<'_, str>
+    where
+        T: AsRef<str>,
+    {
         match &self.nodes[root.idx()] {
-            Node::Set(set) => format!("[{}]{}", set, is_neg,),
-            Node::Union(children) => {
-                let sets: Vec<_> = children.iter().map(|&id| self.to_string(&id)).collect();
-                format!("({}){}", sets.join(" | "), is_neg,)
+            Node::Set(set) if !root.is_neg() => Cow::Borrowed(set.as_ref()),
+            _ => Cow::Owned(self.to_string(root)),
+        }
+    }
+
+    /// Formats the root in negation normal form, for human-facing rule descriptions.
+    ///
+    /// Negations are pushed down to the leaves with De Morgan's laws, so `!` only ever
+    /// prefixes a leaf. Parentheses are only written where a group's operator differs from
+    /// its parent's, and leaves are printed without brackets.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use logify::Expression;
+    /// let mut expr = Expression::new();
+    /// let admin = expr.set("Admin");
+    /// let banned = expr.set("Banned");
+    /// let owner = expr.set("Owner");
+    /// let not_owner = expr.complement(owner);
+    /// let blocked = expr.intersection([banned, not_owner]);
+    /// let not_blocked = expr.complement(blocked);
+    /// let root = expr.intersection([admin, not_blocked]);
+    ///
+    /// assert_eq!(expr.to_string(&root), "([Admin] & ([Banned] & [Owner]')')");
+    /// assert_eq!(expr.to_nnf_string(&root), "Admin & (!Banned | Owner)");
+    /// ```
+    pub fn to_nnf_string(&self, root: &NodeId) -> String {
+        self.to_infix_string(*root, true)
+    }
+
+    /// Formats the root with as few parentheses as precedence allows, for human-facing display.
+    ///
+    /// Precedence is `!`, then `&`, then `|`, so only a Union inside an Intersection and a
+    /// negated group are parenthesized. Negations stay where they are in the graph, unlike
+    /// [`to_nnf_string`](Self::to_nnf_string). Leaves are printed without brackets, unless
+    /// they are empty or contain whitespace or operator characters.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use logify::Expression;
+    /// let mut expr = Expression::new();
+    /// let a = expr.set("A");
+    /// let b = expr.set("B");
+    /// let ab = expr.union([a, b]);
+    /// let c = expr.set("C");
+    /// let not_c = expr.complement(c);
+    /// let root = expr.intersection([ab, not_c]);
+    ///
+    /// assert_eq!(expr.to_string(&root), "(([A] | [B]) & [C]')");
+    /// assert_eq!(expr.to_string_pretty(&root), "(A | B) & !C");
+    ///
+    /// let not_root = expr.complement(root);
+    /// let tag = expr.set("Is Admin");
+    /// let either = expr.union([not_root, tag]);
+    /// assert_eq!(expr.to_string_pretty(&either), "!((A | B) & !C) | [Is Admin]");
+    /// ```
+    pub fn to_string_pretty(&self, root: &NodeId) -> String {
+        self.to_infix_string(*root, false)
+    }
+
+    // infix with only the parentheses precedence needs; in negation normal form, negations are
+    // pushed down to the leaves and leaves are written as-is
+    fn to_infix_string(&self, root: NodeId, nnf: bool) -> String {
+        // nodes still to write with their enclosing group's operator, `true` for a union, and
+        // separators or closing parentheses to write between them
+        enum Pending<'a> {
+            Node(NodeId, Option<bool>),
+            Text(&'a str),
+        }
+
+        let mut out = String::new();
+        let mut stack = vec![Pending::Node(root, None)];
+        while let Some(pending) = stack.pop() {
+            let (id, parent) = match pending {
+                Pending::Node(id, parent) => (id, parent),
+                Pending::Text(text) => {
+                    out.push_str(text);
+                    continue;
+                }
+            };
+            let (is_union, kids) = match &self.nodes[id.idx()] {
+                Node::Empty if id.is_neg() => {
+                    out.push_str("UNIVERSAL");
+                    continue;
+                }
+                Node::Empty => {
+                    out.push_str("EMPTY");
+                    continue;
+                }
+                Node::Set(set) => {
+                    if id.is_neg() {
+                        out.push('!');
+                    }
+                    let text = set.to_string();
+                    let bare = !text.is_empty()
+                        && !text
+                            .chars()
+                            .any(|c| c.is_whitespace() || "()[]&|!^'\"".contains(c));
+                    if nnf || bare {
+                        out.push_str(&text);
+                    } else {
+                        out.push_str(&format!("[{}]", text));
+                    }
+                    continue;
+                }
+                Node::Union(kids) => (true, kids),
+                Node::Intersection(kids) => (false, kids),
+            };
+
+            // in negation normal form, !(A | B) is written !A & !B, and !(A & B) as !A | !B
+            let flip = nnf && id.is_neg();
+            let is_union = is_union != flip;
+            let paren = if nnf {
+                parent.is_some_and(|p| p != is_union)
+            } else {
+                // a negated group is parenthesized anyway, so its children start a new context
+                id.is_neg() || (is_union && parent == Some(false))
+            };
+            if id.is_neg() && !nnf {
+                out.push('!');
             }
-            Node::Intersection(children) => {
-                let sets: Vec<_> = children.iter().map(|&id| self.to_string(&id)).collect();
-                format!("({}){}", sets.join(" & "), is_neg,)
+            if paren {
+                out.push('(');
+                stack.push(Pending::Text(")"));
             }
-            Node::Empty => {
-                if root.is_neg() {
-                    "UNIVERSAL".to_string()
-                } else {
-                    "EMPTY".to_string()
+
+            // pushed in reverse, so they pop in reading order
+            for (i, &kid) in kids.iter().enumerate().rev() {
+                let kid = if flip { kid.not() } else { kid };
+                stack.push(Pending::Node(kid, Some(is_union)));
+                if i > 0 {
+                    stack.push(Pending::Text(if is_union { " | " } else { " & " }));
                 }
             }
         }
+        out
     }
 }
@@ -0,0 +1,149 @@
+use std::hash::Hash;
+
+use crate::expr::{Expression, Node, NodeId};
+
+/// A variable with a polarity, as used in the clauses of a [`Cnf`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Literal {
+    /// The variable index. Indices below `Cnf::terms.len()` are terms, the rest are auxiliary.
+    pub var: usize,
+    /// `true` if the variable appears negated.
+    pub neg: bool,
+}
+
+impl Literal {
+    fn of(var: usize, neg: bool) -> Self {
+        Self { var, neg }
+    }
+
+    fn not(self) -> Self {
+        Self::of(self.var, !self.neg)
+    }
+}
+
+/// A Tseitin encoding of an [`Expression`], as returned by [`Expression::to_cnf`].
+///
+/// The clauses only *define* every node: each auxiliary variable is forced to equal the node
+/// it stands for. Nothing is asserted about the roots, so to require a root to be true, add
+/// `vec![cnf.roots[i]]` as a unit clause.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cnf<'a, T> {
+    /// The clauses, each a disjunction of literals, all of which must hold.
+    pub clauses: Vec<Vec<Literal>>,
+    /// The term behind each of the first `terms.len()` variables.
+    pub terms: Vec<&'a T>,
+    /// The literal standing for each root, in root order.
+    pub roots: Vec<Literal>,
+    /// The total number of variables, terms and auxiliaries included.
+    pub var_count: usize,
+}
+
+impl<T: Hash + PartialEq, Meta> Expression<T, Meta> {
+    /// Converts the live expression to Conjunctive Normal Form with the Tseitin transformation.
+    ///
+    /// Instead of distributing ANDs over ORs, which can grow exponentially, every Union and
+    /// Intersection gets one auxiliary variable with a few defining clauses, so the output is
+    /// linear in the size of the graph. Shared subexpressions are encoded once, no matter how
+    /// many parents use them. A constant root gets its own auxiliary variable, fixed by a unit
+    /// clause.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{Expression, expr::Literal};
+    ///
+    /// let mut expr = Expression::new();
+    /// let a = expr.set("A");
+    /// let b = expr.set("B");
+    /// let root = expr.intersection([a, b]);
+    /// expr.add_root(root);
+    ///
+    /// let cnf = expr.to_cnf();
+    /// assert_eq!(cnf.terms, [&"A", &"B"]);
+    /// assert_eq!(cnf.var_count, 3); // A, B, and the AND
+    ///
+    /// let lit = |var, neg| Literal { var, neg };
+    /// assert_eq!(cnf.roots, [lit(2, false)]);
+    /// assert_eq!(
+    ///     cnf.clauses,
+    ///     [
+    ///         vec![lit(2, true), lit(0, false)],             // AND -> A
+    ///         vec![lit(2, true), lit(1, false)],             // AND -> B
+    ///         vec![lit(2, false), lit(0, true), lit(1, true)], // A & B -> AND
+    ///     ]
+    /// );
+    /// ```
+    pub fn to_cnf(&self) -> Cnf<'_, T> {
+        let (active, max_root) = self.get_active();
+        let mut vars = vec![usize::MAX; max_root + 1];
+
+        // terms take the first variables, in node order
+        let mut terms = Vec::new();
+        for idx in 1..=max_root {
+            if let (true, Node::Set(value)) = (active[idx], &self.nodes[idx]) {
+                vars[idx] = terms.len();
+                terms.push(value);
+            }
+        }
+
+        // children come before parents, so their variables always exist
+        let mut var_count = terms.len();
+        let mut clauses = Vec::new();
+        let lit = |vars: &[usize], id: NodeId| Literal::of(vars[id.idx()], id.is_neg());
+        for idx in 1..=max_root {
+            if !active[idx] {
+                continue;
+            }
+            let (is_union, kids) = match &self.nodes[idx] {
+                Node::Union(kids) => (true, kids),
+                Node::Intersection(kids) => (false, kids),
+                _ => continue,
+            };
+            let x = Literal::of(var_count, false);
+            vars[idx] = var_count;
+            var_count += 1;
+
+            let kids = kids.iter().map(|&k| lit(&vars, k));
+            if is_union {
+                // k -> x for each kid, and x -> some kid
+                let mut any = vec![x.not()];
+                for k in kids {
+                    clauses.push(vec![x, k.not()]);
+                    any.push(k);
+                }
+                clauses.push(any);
+            } else {
+                // x -> k for each kid, and all kids -> x
+                let mut all = vec![x];
+                for k in kids {
+                    clauses.push(vec![x.not(), k]);
+                    all.push(k.not());
+                }
+                clauses.push(all);
+            }
+        }
+
+        let roots = self
+            .roots
+            .iter()
+            .map(|&root| {
+                if root.idx() != 0 {
+                    return lit(&vars, root);
+                }
+                // constants get a variable fixed to false
+                if vars[0] == usize::MAX {
+                    vars[0] = var_count;
+                    var_count += 1;
+                    clauses.push(vec![Literal::of(vars[0], true)]);
+                }
+                lit(&vars, root)
+            })
+            .collect();
+
+        Cnf {
+            clauses,
+            terms,
+            roots,
+            var_count,
+        }
+    }
+}
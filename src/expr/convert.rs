@@ -4,26 +4,238 @@ use crate::expr::{Expression, Node};
 
 #[cfg(feature = "fast-binary")]
 mod impl_fast_binary {
-    use std::hash::Hash;
+    use std::{
+        hash::Hash,
+        io::{self, Read, Write},
+    };
 
-    use crate::expr::{Expression, ExpressionShadow};
+    use crate::expr::{DecodeError, Expression, ExpressionShadow, Node, NodeId};
     use bitcode::{Decode, Encode};
 
-    impl<T: Encode> Expression<T> {
-        pub fn to_bitcode_bytes(expr: &Expression<T>) -> Vec<u8> {
-            bitcode::encode(expr)
+    // precedes the bitcode payload since root metadata was added; bytes without it were
+    // written before then and use the `LegacyShadow` layout
+    const BITCODE_HEADER: &[u8] = b"LGFY\x01";
+
+    #[derive(Decode)]
+    struct LegacyShadow<T> {
+        nodes: Vec<Node<T>>,
+        roots: Vec<NodeId>,
+        uuid: u128,
+        generation: u64,
+    }
+
+    impl<T: Encode, Meta: Encode> Expression<T, Meta> {
+        /// Encodes the expression, including its root metadata, as a versioned bitcode blob.
+        pub fn to_bitcode_bytes(expr: &Expression<T, Meta>) -> Vec<u8> {
+            let mut bytes = BITCODE_HEADER.to_vec();
+            bytes.extend(bitcode::encode(expr));
+            bytes
+        }
+
+        /// Writes the expression to `w` one node at a time.
+        ///
+        /// Unlike [`to_bitcode_bytes`](Self::to_bitcode_bytes), the encoded form is never held in
+        /// memory as a whole; only one node is buffered at a time. The stream is the UUID and
+        /// generation, then a count followed by each node as a length-prefixed bitcode record,
+        /// then a count followed by the raw root IDs, then the root metadata as one more
+        /// length-prefixed record. All integers are little endian.
+        ///
+        /// # Example
+        /// ```rust
+        /// use logify::{Expression, ExpressionBuilder};
+        ///
+        /// let builder = ExpressionBuilder::<String>::new();
+        /// builder.add_root(builder.leaf("A") & !builder.leaf("B"));
+        /// let expr = builder.build();
+        ///
+        /// let mut bytes = Vec::new();
+        /// expr.serialize_to(&mut bytes).unwrap();
+        ///
+        /// let loaded: Expression<String> = Expression::deserialize_from(&mut &bytes[..]).unwrap();
+        /// let root = loaded.roots().next().unwrap();
+        /// assert_eq!(loaded.to_string(root), "([A] & [B]')");
+        /// ```
+        pub fn serialize_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+            w.write_all(&self.uuid.to_le_bytes())?;
+            w.write_all(&self.generation.to_le_bytes())?;
+
+            w.write_all(&(self.nodes.len() as u64).to_le_bytes())?;
+            for node in &self.nodes {
+                let bytes = bitcode::encode(node);
+                w.write_all(&(bytes.len() as u64).to_le_bytes())?;
+                w.write_all(&bytes)?;
+            }
+
+            w.write_all(&(self.roots.len() as u64).to_le_bytes())?;
+            for root in &self.roots {
+                w.write_all(&root.raw().to_le_bytes())?;
+            }
+
+            let bytes = bitcode::encode(&self.meta);
+            w.write_all(&(bytes.len() as u64).to_le_bytes())?;
+            w.write_all(&bytes)?;
+            Ok(())
+        }
+    }
+
+    impl<T, Meta> Expression<T, Meta>
+    where
+        T: Hash + PartialEq + for<'a> Decode<'a>,
+        Meta: Default + for<'a> Decode<'a>,
+    {
+        /// Decodes an expression encoded with [`to_bitcode_bytes`](Self::to_bitcode_bytes).
+        ///
+        /// The graph is validated like any deserialized expression, so corrupted or
+        /// hand-crafted bytes are rejected rather than loaded. Bytes written before root
+        /// metadata existed are still accepted, and every root gets `Meta::default()`.
+        ///
+        /// # Errors
+        /// Returns [`DecodeError::Encoding`], carrying bitcode's error as its source, if the
+        /// bytes fail to decode, or another [`DecodeError`] if they decode to an invalid graph.
+        ///
+        /// # Example
+        /// ```rust
+        /// use logify::{Expression, expr::{DecodeError, Node}};
+        ///
+        /// let mut expr = Expression::new();
+        /// let a = expr.set("A".to_string());
+        /// let b = expr.set("B".to_string());
+        /// let root = expr.union([a, b]);
+        /// expr.add_root(root);
+        ///
+        /// // a group that contains itself
+        /// let mut nodes: Vec<_> = expr.nodes().cloned().collect();
+        /// nodes[root.index()] = Node::Union(vec![a, root]);
+        /// let bad = Expression::from_nodes_unchecked(nodes, vec![root]);
+        ///
+        /// let bytes = Expression::to_bitcode_bytes(&bad);
+        /// let loaded = Expression::<String>::from_bitcode_bytes(&bytes);
+        /// assert!(matches!(
+        ///     loaded.err(),
+        ///     Some(DecodeError::ForwardReference { node: 3, child: 3 })
+        /// ));
+        ///
+        /// // a group with no children, which evaluators cannot combine
+        /// let mut nodes: Vec<_> = expr.nodes().cloned().collect();
+        /// nodes[root.index()] = Node::Intersection(vec![]);
+        /// let bad = Expression::from_nodes_unchecked(nodes, vec![root]);
+        /// let bytes = Expression::to_bitcode_bytes(&bad);
+        /// let loaded = Expression::<String>::from_bitcode_bytes(&bytes);
+        /// assert!(matches!(loaded.err(), Some(DecodeError::EmptyGroup { node: 3 })));
+        ///
+        /// // bitcode's own error is kept as the source
+        /// use std::error::Error;
+        /// let garbage = Expression::<String>::from_bitcode_bytes(b"LGFY\x01garbage");
+        /// let err = garbage.err().unwrap();
+        /// assert!(matches!(err, DecodeError::Encoding(_)));
+        /// assert!(err.source().is_some());
+        ///
+        /// // written by a release without root metadata: roots `[A] & [B]'` and `[C]`
+        /// let old = [
+        ///     5, 212, 1, 10, 7, 65, 66, 67, 2, 4, 2, 5, 2, 4, 6, 8, 0, 139, 98, 116, 213, 1, 187, 67,
+        ///     93, 45, 81, 27, 88, 94, 165, 247, 49, 6, 0,
+        /// ];
+        /// let loaded = Expression::<String>::from_bitcode_bytes(&old).ok().unwrap();
+        /// let roots: Vec<_> = loaded.roots().map(|r| loaded.to_string(r)).collect();
+        /// assert_eq!(roots, ["([A] & [B]')", "[C]"]);
+        /// ```
+        pub fn from_bitcode_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+            let shadow = match bytes.strip_prefix(BITCODE_HEADER) {
+                Some(payload) => bitcode::decode(payload),
+                None => bitcode::decode(bytes).map(|legacy: LegacyShadow<T>| ExpressionShadow {
+                    nodes: legacy.nodes,
+                    roots: legacy.roots,
+                    meta: Vec::new(),
+                    uuid: legacy.uuid,
+                    generation: legacy.generation,
+                }),
+            };
+            shadow.map_err(DecodeError::Encoding)?.try_into()
+        }
+
+        /// Reads an expression written by [`serialize_to`](Self::serialize_to).
+        ///
+        /// # Errors
+        /// Returns any error from `r`, or [`io::ErrorKind::InvalidData`] if a node or the
+        /// metadata fails to decode, or the nodes don't form a valid graph (see [`DecodeError`]).
+        /// Record lengths are not trusted for allocation, so a stream that claims a huge record
+        /// fails once it runs out of bytes.
+        ///
+        /// # Example
+        /// ```rust
+        /// use logify::{Expression, ExpressionBuilder};
+        /// use std::io::ErrorKind;
+        ///
+        /// let builder = ExpressionBuilder::<String>::new();
+        /// builder.add_root(builder.leaf("A"));
+        /// let mut bytes = Vec::new();
+        /// builder.build().serialize_to(&mut bytes).unwrap();
+        ///
+        /// // after the UUID, generation and node count, claim a first node of u64::MAX / 2 bytes
+        /// bytes[32..40].copy_from_slice(&(u64::MAX / 2).to_le_bytes());
+        /// let loaded = Expression::<String>::deserialize_from(&mut &bytes[..]);
+        /// assert_eq!(loaded.err().map(|e| e.kind()), Some(ErrorKind::UnexpectedEof));
+        /// ```
+        pub fn deserialize_from<R: Read>(r: &mut R) -> io::Result<Self> {
+            let uuid = u128::from_le_bytes(read_array(r)?);
+            let generation = u64::from_le_bytes(read_array(r)?);
+
+            // counts come from the stream, so don't trust them for preallocation
+            let node_count = read_len(r)?;
+            let mut nodes = Vec::with_capacity(node_count.min(1024));
+            let mut buf = Vec::new();
+            for _ in 0..node_count {
+                read_record(r, &mut buf)?;
+                let node: Node<T> = bitcode::decode(&buf)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                nodes.push(node);
+            }
+
+            let root_count = read_len(r)?;
+            let mut roots = Vec::with_capacity(root_count.min(1024));
+            for _ in 0..root_count {
+                roots.push(NodeId(u32::from_le_bytes(read_array(r)?)));
+            }
+
+            read_record(r, &mut buf)?;
+            let meta =
+                bitcode::decode(&buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+            ExpressionShadow {
+                nodes,
+                roots,
+                meta,
+                uuid,
+                generation,
+            }
+            .try_into()
+            .map_err(|e: DecodeError| io::Error::new(io::ErrorKind::InvalidData, e))
         }
     }
 
-    impl<T: Hash + PartialEq + for<'a> Decode<'a>> Expression<T> {
-        pub fn from_bitcode_bytes(bytes: &[u8]) -> Result<Self, bitcode::Error> {
-            let shadow: ExpressionShadow<T> = bitcode::decode(bytes)?;
-            Ok(shadow.into())
+    fn read_array<R: Read, const N: usize>(r: &mut R) -> io::Result<[u8; N]> {
+        let mut bytes = [0; N];
+        r.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn read_len<R: Read>(r: &mut R) -> io::Result<usize> {
+        let len = u64::from_le_bytes(read_array(r)?);
+        usize::try_from(len).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    // reads a length-prefixed record into `buf`, growing it only as bytes actually arrive
+    fn read_record<R: Read>(r: &mut R, buf: &mut Vec<u8>) -> io::Result<()> {
+        let len = u64::from_le_bytes(read_array(r)?);
+        buf.clear();
+        if r.by_ref().take(len).read_to_end(buf)? as u64 != len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
         }
+        Ok(())
     }
 }
 
-impl<T> IntoIterator for Expression<T> {
+impl<T, Meta> IntoIterator for Expression<T, Meta> {
     type Item = Self;
     type IntoIter = std::iter::Once<Self>;
     fn into_iter(self) -> Self::IntoIter {
@@ -31,21 +243,46 @@ impl<T> IntoIterator for Expression<T> {
     }
 }
 
-impl<T: Hash + PartialEq> Extend<Expression<T>> for Expression<T> {
-    fn extend<I: IntoIterator<Item = Expression<T>>>(&mut self, iter: I) {
+/// Moves many expressions into this one, sharing identical logic between them.
+///
+/// Each source's roots are appended in order. Only live nodes are copied, and every node
+/// goes through interning, so a subexpression that appears in several sources is stored
+/// once. Prefer this over [`absorb_raw`](Expression::absorb_raw) when the sources overlap
+/// heavily or carry dead nodes.
+///
+/// # Example
+/// ```rust
+/// let sources = (0..3).map(|i| {
+///     let mut expr = logify::Expression::new();
+///     let a = expr.set("A".to_string());
+///     let b = expr.set("B".to_string());
+///     let ab = expr.union([a, b]);
+///     let own = expr.set(format!("Rule{}", i));
+///     let root = expr.intersection([ab, own]);
+///     expr.add_root(root);
+///     expr
+/// });
+///
+/// let mut corpus = logify::Expression::new();
+/// corpus.extend(sources);
+///
+/// assert_eq!(corpus.root_count(), 3);
+/// // Empty, A, B, (A | B), plus a leaf and an AND per rule
+/// assert_eq!(corpus.node_count(), 4 + 3 * 2);
+/// ```
+impl<T: Hash + PartialEq, Meta> Extend<Expression<T, Meta>> for Expression<T, Meta> {
+    fn extend<I: IntoIterator<Item = Expression<T, Meta>>>(&mut self, iter: I) {
         for mut source in iter {
-            if source.nodes.len() == 1 {
-                continue;
-            }
             let (active, max_root) = source.get_active();
-            self.absorb(&active, max_root, &source.roots, |idx| {
+            let meta = mem::take(&mut source.meta);
+            self.absorb(&active, max_root, &source.roots, meta, |idx| {
                 mem::replace(&mut source.nodes[idx], Node::Empty)
             });
         }
     }
 }
 
-impl<T> IntoIterator for &Expression<T> {
+impl<T, Meta> IntoIterator for &Expression<T, Meta> {
     type Item = Self;
     type IntoIter = std::iter::Once<Self>;
     fn into_iter(self) -> Self::IntoIter {
@@ -53,14 +290,14 @@ impl<T> IntoIterator for &Expression<T> {
     }
 }
 
-impl<'a, T: Clone + Hash + PartialEq> Extend<&'a Expression<T>> for Expression<T> {
-    fn extend<I: IntoIterator<Item = &'a Expression<T>>>(&mut self, iter: I) {
+impl<'a, T: Clone + Hash + PartialEq, Meta: Clone> Extend<&'a Expression<T, Meta>>
+    for Expression<T, Meta>
+{
+    fn extend<I: IntoIterator<Item = &'a Expression<T, Meta>>>(&mut self, iter: I) {
         for source in iter {
-            if source.nodes.len() == 1 {
-                continue;
-            }
             let (active, max_root) = source.get_active();
-            self.absorb(&active, max_root, &source.roots, |idx| {
+            let meta = source.meta.iter().cloned();
+            self.absorb(&active, max_root, &source.roots, meta, |idx| {
                 source.nodes[idx].clone()
             });
         }
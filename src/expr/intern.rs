@@ -0,0 +1,42 @@
+use std::hash::{BuildHasher, Hash};
+
+use rapidhash::{fast, quality};
+
+/// The hash behind an expression's interning table.
+///
+/// Quality is the default. Fast is chosen per expression with
+/// [`Expression::with_fast_hash`](crate::Expression::with_fast_hash), and is kept by clones
+/// and by the expressions that pruning and rebuilding produce.
+#[derive(Clone, Copy)]
+pub(crate) enum InternState {
+    Quality(quality::RandomState),
+    Fast(fast::RandomState),
+}
+
+impl Default for InternState {
+    fn default() -> Self {
+        Self::Quality(quality::RandomState::new())
+    }
+}
+
+impl InternState {
+    pub(crate) fn fast() -> Self {
+        Self::Fast(fast::RandomState::new())
+    }
+
+    // picks the variant once per value rather than per write, so each hashes at full speed
+    pub(crate) fn hash_one<H: Hash>(&self, value: H) -> u64 {
+        match self {
+            Self::Quality(state) => state.hash_one(value),
+            Self::Fast(state) => state.hash_one(value),
+        }
+    }
+
+    // a freshly seeded state of the same kind
+    pub(crate) fn reseeded(&self) -> Self {
+        match self {
+            Self::Quality(_) => Self::default(),
+            Self::Fast(_) => Self::fast(),
+        }
+    }
+}
@@ -13,16 +13,16 @@ use crate::expr::{Expression, Node, NodeId};
 /// * **Deduplicated:** Shared nodes (diamonds in the graph) are yielded exactly once.
 /// * **Pruned:** Only nodes reachable from the `Expression`'s roots are visited.
 pub struct ExpressionDependencyIter<'a, T> {
-    expr: &'a Expression<T>,
+    nodes: &'a [Node<T>],
     stack: Vec<(NodeId, bool)>,
     visited: Vec<bool>, // TODO: would a bitset be faster?
 }
 
 impl<'a, T> ExpressionDependencyIter<'a, T> {
-    pub(crate) fn new(expr: &'a Expression<T>) -> Self {
+    pub(crate) fn new<Meta>(expr: &'a Expression<T, Meta>) -> Self {
         let stack = expr.roots.iter().map(|&id| (id, false)).collect();
         Self {
-            expr,
+            nodes: &expr.nodes,
             stack,
             visited: vec![false; expr.nodes.len()],
         }
@@ -39,11 +39,11 @@ impl<'a, T> Iterator for ExpressionDependencyIter<'a, T> {
             }
             if expanded {
                 self.visited[id.idx()] = true;
-                return Some((id, &self.expr.nodes[id.idx()]));
+                return Some((id, &self.nodes[id.idx()]));
             } else {
                 // mark self as expanded, visit children first
                 self.stack.push((id, true));
-                match &self.expr.nodes[id.idx()] {
+                match &self.nodes[id.idx()] {
                     Node::Union(kids) | Node::Intersection(kids) => {
                         for &k in kids.iter().rev() {
                             if !self.visited[k.idx()] {
@@ -0,0 +1,210 @@
+use std::hash::Hash;
+
+use crate::{
+    eval::{Evaluator, NodeList, NodeRef},
+    expr::{Expression, Node, NodeId},
+};
+
+/// A frozen, minimal-footprint copy of an [`Expression`].
+///
+/// An `Expression` carries an interning map, a UUID, and spare vector capacity so that it can be
+/// edited and cached efficiently. For tiny expressions stored by the million, that overhead
+/// dwarfs the logic itself. `TinyExpr` keeps only the live nodes, with the children of every
+/// group stored back to back in one shared slice, so groups need no allocation of their own.
+/// Root metadata is kept alongside the roots, and costs nothing when `Meta` is `()`.
+///
+/// It can be evaluated directly, but not edited. Convert it back with
+/// [`Expression::from_tiny`] to make changes.
+///
+/// # Example
+/// ```rust
+/// use logify::{Expression, ExpressionBuilder, eval::BoolEval};
+///
+/// let builder = ExpressionBuilder::new();
+/// builder.add_root(builder.leaf("A") & !builder.leaf("B"));
+/// let expr = builder.build();
+///
+/// let tiny = expr.to_tiny();
+///
+/// let mut ctx = BoolEval::new();
+/// ctx.add("A");
+/// assert_eq!(tiny.evaluate(&mut ctx), Ok(vec![true]));
+///
+/// // Thaw to edit again
+/// let expr = Expression::from_tiny(tiny);
+/// let root = expr.roots().next().unwrap();
+/// assert_eq!(expr.to_string(root), "([A] & [B]')");
+/// ```
+#[derive(Clone)]
+pub struct TinyExpr<T, Meta = ()> {
+    nodes: Box<[TinyNode<T>]>,
+    kids: Box<[NodeId]>,
+    roots: Box<[NodeId]>,
+    meta: Box<[Meta]>,
+}
+
+// a node whose children are the range `start..start + len` of the shared slice
+#[derive(Clone)]
+enum TinyNode<T> {
+    Empty,
+    Set(T),
+    Union(u32, u32),
+    Intersection(u32, u32),
+}
+
+impl<T, Meta> TinyExpr<T, Meta> {
+    /// Iterate over the root IDs.
+    pub fn roots(&self) -> std::slice::Iter<'_, NodeId> {
+        self.roots.iter()
+    }
+
+    /// Returns the metadata of every root, aligned with [`roots`](Self::roots).
+    pub fn root_metas(&self) -> &[Meta] {
+        &self.meta
+    }
+
+    /// Returns the number of stored nodes.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Evaluates every root, without any persistent cache.
+    pub fn evaluate<R, E, S>(&self, solver: &mut S) -> Result<Vec<R>, E>
+    where
+        R: Clone,
+        S: Evaluator<T, R, E>,
+    {
+        let mut store = vec![None; self.nodes.len() * 2];
+        let mut active = Vec::new();
+        let mut include_indices = Vec::new();
+        let mut exclude_indices = Vec::new();
+        Expression::<T>::fill_store(
+            self,
+            &self.roots,
+            solver,
+            &mut store,
+            &mut active,
+            &mut include_indices,
+            &mut exclude_indices,
+        )?;
+        Expression::<T>::collect_roots(&self.roots, solver, &mut store)
+    }
+
+    fn kids(&self, start: u32, len: u32) -> &[NodeId] {
+        &self.kids[start as usize..(start + len) as usize]
+    }
+}
+
+impl<T, Meta> NodeList<T> for TinyExpr<T, Meta> {
+    #[inline]
+    fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    #[inline]
+    fn node(&self, idx: usize) -> NodeRef<'_, T> {
+        match &self.nodes[idx] {
+            TinyNode::Empty => NodeRef::Empty,
+            TinyNode::Set(set) => NodeRef::Set(set),
+            TinyNode::Union(start, len) => NodeRef::Union(self.kids(*start, *len)),
+            TinyNode::Intersection(start, len) => NodeRef::Intersection(self.kids(*start, *len)),
+        }
+    }
+}
+
+impl<T: Clone + Hash + PartialEq, Meta: Clone> Expression<T, Meta> {
+    /// Copies the live part of the expression into a compact, read-only [`TinyExpr`].
+    ///
+    /// Dead nodes are dropped, so the root IDs of the result may differ from this expression's.
+    /// Root metadata is copied along with the roots.
+    ///
+    /// # Panics
+    /// Panics if the live groups hold more than `u32::MAX` children in total.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::Expression;
+    ///
+    /// let mut expr = Expression::<&str, u32>::default();
+    /// let a = expr.set("A");
+    /// let b = expr.set("B");
+    /// let root = expr.union([a, b]);
+    /// expr.add_root_with_meta(root, 7);
+    ///
+    /// let tiny = expr.to_tiny();
+    /// assert_eq!(tiny.root_metas(), [7]);
+    /// assert_eq!(Expression::from_tiny(tiny).root_meta(0), &7);
+    /// ```
+    pub fn to_tiny(&self) -> TinyExpr<T, Meta> {
+        let pruned = self.clone().prune::<()>();
+        let mut kids = Vec::new();
+        let mut range = |group: Vec<NodeId>| {
+            let start = kids.len() as u32; // the previous end, so it fits
+            kids.extend(group);
+            let end = u32::try_from(kids.len()).expect("too many children for a TinyExpr");
+            (start, end - start)
+        };
+        let nodes = pruned
+            .nodes
+            .into_iter()
+            .map(|node| match node {
+                Node::Empty => TinyNode::Empty,
+                Node::Set(value) => TinyNode::Set(value),
+                Node::Union(group) => {
+                    let (start, len) = range(group);
+                    TinyNode::Union(start, len)
+                }
+                Node::Intersection(group) => {
+                    let (start, len) = range(group);
+                    TinyNode::Intersection(start, len)
+                }
+            })
+            .collect();
+        TinyExpr {
+            nodes,
+            kids: kids.into_boxed_slice(),
+            roots: pruned.roots.into_boxed_slice(),
+            meta: pruned.meta.into_boxed_slice(),
+        }
+    }
+}
+
+impl<T: Hash + PartialEq, Meta> Expression<T, Meta> {
+    /// Rebuilds an editable Expression from a [`TinyExpr`], keeping its root metadata.
+    pub fn from_tiny(tiny: TinyExpr<T, Meta>) -> Self {
+        let mut expr = Expression::default();
+        let mut map = vec![NodeId::EMPTY; tiny.nodes.len()];
+        let remap = |map: &[NodeId], id: NodeId| {
+            let new = map[id.idx()];
+            if id.is_neg() { new.not() } else { new }
+        };
+
+        // children always come before their parents
+        let TinyExpr {
+            nodes,
+            kids,
+            roots,
+            meta,
+        } = tiny;
+        let group = |start: u32, len: u32| &kids[start as usize..(start + len) as usize];
+        for (idx, node) in nodes.into_vec().into_iter().enumerate().skip(1) {
+            map[idx] = match node {
+                TinyNode::Empty => NodeId::EMPTY,
+                TinyNode::Set(value) => expr.set(value),
+                TinyNode::Union(start, len) => {
+                    let kids: Vec<_> = group(start, len).iter().map(|&k| remap(&map, k)).collect();
+                    expr.union(kids)
+                }
+                TinyNode::Intersection(start, len) => {
+                    let kids: Vec<_> = group(start, len).iter().map(|&k| remap(&map, k)).collect();
+                    expr.intersection(kids)
+                }
+            };
+        }
+        for (&root, meta) in roots.iter().zip(meta.into_vec()) {
+            let root = remap(&map, root);
+            expr.add_root_with_meta(root, meta);
+        }
+        expr
+    }
+}
@@ -5,14 +5,39 @@ pub mod builder;
 pub mod eval;
 pub mod expr;
 pub mod opt;
+pub mod parse;
 
+/// Common imports for building, optimizing, and evaluating expressions.
+///
+/// `any![...]` and `all![...]` are not standalone macros; they are syntax understood by
+/// [`logic!`](crate::logic), so importing `logic` is enough to use them.
+///
+/// # Example
+/// ```rust
+/// use logify::prelude::*;
+///
+/// let builder = ExpressionBuilder::<&str>::new();
+/// let root = logic!(builder, any!["Admin", all!["Editor", !"Suspended"]]);
+/// builder.add_root(root);
+/// let expr = builder.build();
+///
+/// let mut solver = BoolEval::new();
+/// solver.add("Editor");
+/// assert_eq!(expr.evaluate(&mut solver), Ok(vec![true]));
+///
+/// let mut solver = BitwiseEval::new(0b11u8);
+/// solver.insert("Admin", 0b01);
+/// solver.insert("Editor", 0b11);
+/// solver.insert("Suspended", 0b11);
+/// assert_eq!(expr.evaluate(&mut solver), Ok(vec![0b01]));
+/// ```
 pub mod prelude {
     pub use crate::builder::ExpressionBuilder;
-    pub use crate::eval::{Evaluator, EvaluatorCache};
+    pub use crate::eval::{BitwiseEval, BoolEval, Evaluator, EvaluatorCache};
     pub use crate::expr::{Expression, Node, NodeId};
     pub use crate::opt::{MergeResult, Mergeable, OptimizerConfig, SetRelation};
 
-    pub use crate::logic;
+    pub use crate::{logic, truth_table};
 }
 
 pub use builder::ExpressionBuilder;
@@ -53,6 +53,31 @@ macro_rules! logic_list {
     };
 }
 
+/// Builds logic visually from leaves, operators, and `any![...]` / `all![...]` groups.
+///
+/// Operators bind like Rust's, tightest first: `!`, `&`, `^`, `|`. Implication, written `=>`
+/// or `->`, binds loosest and groups to the right, so `A & B => C => D` reads as
+/// `(A & B) => (C => D)`.
+///
+/// # Example
+/// ```rust
+/// use logify::{ExpressionBuilder, logic};
+///
+/// let builder = ExpressionBuilder::<&str>::new();
+/// builder.add_root(logic!(builder, "A" | "B" ^ "C"));
+/// builder.add_root(logic!(builder, "A" & "B" => "C"));
+/// builder.add_root(logic!(builder, "A" -> "B" -> "C"));
+/// builder.add_root(logic!(builder, ("A" => "B") & !"C"));
+/// let expr = builder.build();
+///
+/// let roots: Vec<_> = expr.roots().map(|r| expr.to_string(r)).collect();
+/// assert_eq!(roots, [
+///     "([A] | (([B] & [C]') | ([B]' & [C])))",
+///     "([C] | ([A] & [B])')",
+///     "([A]' | ([B]' | [C]))",
+///     "([C]' & ([A]' | [B]))",
+/// ]);
+/// ```
 #[macro_export]
 macro_rules! logic {
     ($builder:ident, $($input:tt)+) => {
@@ -92,14 +117,21 @@ macro_rules! logic {
     (@recurse $b:ident, [ & $($rest:tt)* ] -> [$($out:tt)*]) => {
         $crate::logic!(@recurse $b, [$($rest)*] -> [$($out)* &])
     };
-	// // ^
-    // (@recurse $b:ident, [ ^ $($rest:tt)* ] -> [$($out:tt)*]) => {
-    //     $crate::logic!(@recurse $b, [$($rest)*] -> [$($out)* ^])
-    // };
+	// ^
+    (@recurse $b:ident, [ ^ $($rest:tt)* ] -> [$($out:tt)*]) => {
+        $crate::logic!(@recurse $b, [$($rest)*] -> [$($out)* ^])
+    };
 	// !
     (@recurse $b:ident, [ ! $($rest:tt)* ] -> [$($out:tt)*]) => {
         $crate::logic!(@recurse $b, [$($rest)*] -> [$($out)* !])
     };
+	// => and ->, everything after is the consequent
+    (@recurse $b:ident, [ => $($rest:tt)+ ] -> [$($out:tt)+]) => {
+        ( $($out)+ ).implies($crate::logic!($b, $($rest)+))
+    };
+    (@recurse $b:ident, [ -> $($rest:tt)+ ] -> [$($out:tt)+]) => {
+        ( $($out)+ ).implies($crate::logic!($b, $($rest)+))
+    };
 
     // groups
     (@recurse $b:ident, [ ( $($inner:tt)* ) $($rest:tt)* ] -> [$($out:tt)*]) => {
@@ -120,3 +152,48 @@ macro_rules! logic {
         ])
     };
 }
+
+/// Builds an expression from the rows of a truth table where the output is true.
+///
+/// Each minterm is read with the first variable as the most significant bit, so for
+/// `[a, b, c]` the minterm `0b001` means `!a & !b & c`. The result is the union of every
+/// listed minterm (DNF), returned as a `LogicNode`. Run [`Expression::optimize`](crate::Expression::optimize)
+/// afterwards to simplify it.
+///
+/// # Example
+/// ```rust
+/// use logify::{ExpressionBuilder, truth_table};
+///
+/// let builder = ExpressionBuilder::<&str>::new();
+///
+/// // XOR: true for rows 01 and 10
+/// let xor = truth_table!(builder; ["A", "B"]; minterms = [0b01, 0b10]);
+/// builder.add_root(xor);
+///
+/// let expr = builder.build();
+/// let root = expr.roots().next().unwrap();
+/// assert_eq!(expr.to_string(root), "(([A]' & [B]) | ([A] & [B]'))");
+/// ```
+#[macro_export]
+macro_rules! truth_table {
+    ($b:ident; [ $($var:expr),* $(,)? ]; minterms = [ $($m:expr),* $(,)? ]) => {{
+        let safe_b = $crate::builder::ExpressionBuilder::__check_type(&$b);
+        let vars = [ $( safe_b.set($var) ),* ];
+        let minterms: &[u64] = &[ $($m),* ];
+        let n = vars.len();
+        assert!(n <= 64, "truth_table! supports at most 64 variables");
+        let rows: Vec<_> = minterms
+            .iter()
+            .map(|&m| {
+                safe_b.intersection((0..n).map(|i| {
+                    if (m >> (n - 1 - i)) & 1 == 1 {
+                        vars[i]
+                    } else {
+                        safe_b.not(vars[i])
+                    }
+                }))
+            })
+            .collect();
+        safe_b.wrap(safe_b.union(rows))
+    }};
+}
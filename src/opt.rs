@@ -1,14 +1,20 @@
-use std::hash::Hash;
+use std::{cell::RefCell, hash::Hash};
 
 use crate::{
     expr::{Expression, Node, NodeId},
-    opt::merger::Merger,
+    opt::merger::{Fallible, MergeRelation, Merger},
 };
 
 mod algo;
+mod batch;
+mod check;
+mod explain;
 mod merger;
 
-pub use merger::{MergeResult, Mergeable, SetRelation};
+pub use batch::{BatchHandle, BatchOptimizer};
+pub use check::{Inconsistency, check_merger_consistency};
+pub use explain::{Contradiction, ContradictionKind};
+pub use merger::{MergeResult, Mergeable, SetRelation, TryMergeable};
 
 /// Configuration for the [`Expression::optimize`] method.
 ///
@@ -50,33 +56,250 @@ pub struct OptimizerConfig<M> {
     /// Limiting iterations is rarely necessary as the optimizer converges quickly,
     /// but it can be used to guarantee a strict time budget.
     pub max_iterations: usize,
+
+    /// Also factors intersections: `(A | B) & (A | C)` becomes `A | (B & C)`.
+    ///
+    /// Unions are always factored, as `(A & B) | (A & C)` becomes `A & (B | C)`, which is
+    /// both smaller and cheaper to evaluate. The dual rewrite still shrinks the graph, but moves
+    /// work into unions, which cannot stop early the way intersections can. Enable it when the
+    /// expression size matters more than evaluation speed, such as before serialization.
+    ///
+    /// Defaults to `false`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{Expression, opt::OptimizerConfig};
+    ///
+    /// // (A | B1) & (A | B2) & ... & (A | B8)
+    /// let mut expr = Expression::new();
+    /// let a = expr.set("A".to_string());
+    /// let clauses: Vec<_> = (1..=8)
+    ///     .map(|i| {
+    ///         let b = expr.set(format!("B{i}"));
+    ///         expr.union([a, b])
+    ///     })
+    ///     .collect();
+    /// let root = expr.intersection(clauses);
+    /// expr.add_root(root);
+    /// let before = expr.stats();
+    ///
+    /// let mut config = OptimizerConfig { factor_intersections: true, ..Default::default() };
+    /// expr.optimize(&mut config);
+    /// let after = expr.stats();
+    ///
+    /// // A | (B1 & B2 & ... & B8)
+    /// assert_eq!((before.unions, before.intersections), (8, 1));
+    /// assert_eq!((after.unions, after.intersections), (1, 1));
+    /// ```
+    pub factor_intersections: bool,
+}
+
+/// A summary of what an optimization run did, returned by [`Expression::optimize`].
+///
+/// Node counts are of live nodes (reachable from the roots, excluding the constant node),
+/// so dead nodes left behind by earlier runs do not skew them.
+///
+/// # Example
+/// ```rust
+/// use logify::{ExpressionBuilder, opt::OptimizerConfig};
+///
+/// let builder = ExpressionBuilder::<&str>::new();
+/// let (a, b) = (builder.leaf("A"), builder.leaf("B"));
+/// builder.add_root(a & (a | b));
+/// builder.add_root(a & !(a | b));
+/// let mut expr = builder.build();
+///
+/// let report = expr.optimize(&mut OptimizerConfig::default());
+/// assert_eq!((report.nodes_before, report.nodes_after), (5, 1));
+/// assert!(report.fixed_point);
+/// assert!(report.collapsed_root); // A & !(A | B) is EMPTY
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OptimizeReport {
+    /// Live nodes before optimizing.
+    pub nodes_before: usize,
+    /// Live nodes after optimizing.
+    pub nodes_after: usize,
+    /// Passes run over the expression.
+    pub iterations: usize,
+    /// Whether the expression stopped changing, rather than hitting `max_iterations`
+    /// or another stopping condition first.
+    pub fixed_point: bool,
+    /// Whether any root that was not already constant became `EMPTY` or `UNIVERSAL`.
+    pub collapsed_root: bool,
 }
 
+// depth used when relations are needed outside of a configured optimization
+const DEFAULT_MERGER_DEPTH: usize = 2;
+
 impl Default for OptimizerConfig<()> {
     fn default() -> Self {
         Self {
             merger: (),
-            merger_depth: 2,
+            merger_depth: DEFAULT_MERGER_DEPTH,
             max_iterations: 0,
+            factor_intersections: false,
         }
     }
 }
 
-impl<T: Hash + PartialEq> Expression<T> {
+impl<T: Hash + PartialEq, Meta> Expression<T, Meta> {
     /// Applies logic reduction and domain-specific simplification to the expression.
     ///
     /// This method performs operations such as:
     /// * **Flattening:** `Union(A, Union(B, C))` becomes `Union(A, B, C)`.
     /// * **De Morgan's Laws:** Distributes negations to minimize depth.
     /// * **Absorption:** `A & (A | B)` simplifies to `A`.
+    /// * **Consensus:** `(A & B) | (!A & C) | (B & C)` drops the redundant `(B & C)`. Needs a
+    ///   `merger_depth` of at least 2, and is skipped for unions of more than 32 children since
+    ///   its cost grows with the cube of their number.
     /// * **Custom Merging:** Uses the provided [`Mergeable`] implementation to combine sets.
     ///
+    /// # Example
+    /// ```rust
+    /// use logify::{ExpressionBuilder, opt::OptimizerConfig};
+    ///
+    /// let builder = ExpressionBuilder::<&str>::new();
+    /// let (a, b, c) = (builder.leaf("A"), builder.leaf("B"), builder.leaf("C"));
+    /// builder.add_root((a & b) | (!a & c) | (b & c));
+    /// let mut expr = builder.build();
+    ///
+    /// expr.optimize(&mut OptimizerConfig::default());
+    /// let root = expr.roots().next().unwrap();
+    /// assert_eq!(expr.to_string(root), "(([A] & [B]) | ([A]' & [C]))");
+    /// ```
+    ///
+    /// Negated terms are related to groups exactly, so results always match the original:
+    /// ```rust
+    /// use logify::{ExpressionBuilder, eval::BoolEval, opt::OptimizerConfig};
+    ///
+    /// let builder = ExpressionBuilder::<&str>::new();
+    /// let (a, b, c, d) = (builder.leaf("A"), builder.leaf("B"), builder.leaf("C"), builder.leaf("D"));
+    /// builder.add_root(!c & ((!c & d) | b));
+    /// builder.add_root(!((d | a) & !b) & (!d | !a));
+    /// builder.add_root(b | (c & a) | (!b & !c));
+    /// let original = builder.build();
+    ///
+    /// let mut optimized = original.clone();
+    /// optimized.optimize(&mut OptimizerConfig::default());
+    ///
+    /// for bits in 0..16 {
+    ///     let mut ctx = BoolEval::new();
+    ///     for (i, term) in ["A", "B", "C", "D"].into_iter().enumerate() {
+    ///         if bits >> i & 1 == 1 {
+    ///             ctx.add(term);
+    ///         }
+    ///     }
+    ///     let expected = original.evaluate(&mut ctx.clone());
+    ///     assert_eq!(optimized.evaluate(&mut ctx), expected);
+    /// }
+    /// ```
+    ///
     /// # Dead Nodes
     /// Optimization rewrites connections between nodes. This often leaves behind "dead" nodes
     /// (nodes that are no longer connected to any root). While this does not affect evaluation
     /// correctness, you may wish to call [`Expression::clean`](crate::Expression::clean) afterwards
     /// if memory footprint is a concern.
-    pub fn optimize<M: Mergeable<T>>(&mut self, config: &mut OptimizerConfig<M>) {
+    ///
+    /// Returns an [`OptimizeReport`] describing the run.
+    pub fn optimize<M: Mergeable<T>>(&mut self, config: &mut OptimizerConfig<M>) -> OptimizeReport {
+        self.run_optimizer(config, |_, _| false)
+    }
+
+    /// Like [`optimize`](Self::optimize), but with a merger whose lookups can fail.
+    ///
+    /// The first error from the [`TryMergeable`] is returned once the current pass finishes.
+    /// The rest of that pass runs without domain knowledge, as if every lookup answered
+    /// "no relation", so the expression is always left valid and partially optimized.
+    ///
+    /// On success, returns an [`OptimizeReport`] describing the run.
+    ///
+    /// # Errors
+    /// Returns the first error from `config.merger`.
+    pub fn try_optimize<M: TryMergeable<T>>(
+        &mut self,
+        config: &mut OptimizerConfig<M>,
+    ) -> Result<OptimizeReport, M::Error> {
+        let error = RefCell::new(None);
+        let mut fallible = OptimizerConfig {
+            merger: Fallible {
+                inner: &mut config.merger,
+                error: &error,
+            },
+            merger_depth: config.merger_depth,
+            max_iterations: config.max_iterations,
+            factor_intersections: config.factor_intersections,
+        };
+        let report = self.run_optimizer(&mut fallible, |_, _| error.borrow().is_some());
+        match error.into_inner() {
+            Some(e) => Err(e),
+            None => Ok(report),
+        }
+    }
+
+    /// Optimizes until the expression is small enough, rather than until it stops changing.
+    ///
+    /// The live node count (nodes reachable from the roots, excluding the constant node) is
+    /// checked between passes, and optimization stops as soon as it is at or below
+    /// `target_live_nodes`. Otherwise this behaves like [`optimize`](Self::optimize), including
+    /// honoring `max_iterations`.
+    ///
+    /// Returns `true` if the target was met.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{ExpressionBuilder, opt::OptimizerConfig};
+    ///
+    /// let builder = ExpressionBuilder::<&str>::new();
+    /// let a = builder.leaf("A");
+    /// let b = builder.leaf("B");
+    /// builder.add_root(a & (a | b));
+    /// let mut expr = builder.build();
+    ///
+    /// // A & (A | B) == A
+    /// assert!(expr.optimize_until_size(&mut OptimizerConfig::default(), 1));
+    /// let root = expr.roots().next().unwrap();
+    /// assert_eq!(expr.to_string(root), "[A]");
+    /// ```
+    pub fn optimize_until_size<M: Mergeable<T>>(
+        &mut self,
+        config: &mut OptimizerConfig<M>,
+        target_live_nodes: usize,
+    ) -> bool {
+        if self.live_count(&self.roots) <= target_live_nodes {
+            return true;
+        }
+        let mut met = false;
+        let report = self.run_optimizer(config, |expr, remap| {
+            let roots: Vec<NodeId> = expr.roots.iter().map(|&r| resolve(r, remap)).collect();
+            met = expr.live_count(&roots) <= target_live_nodes;
+            met
+        });
+        met || report.nodes_after <= target_live_nodes
+    }
+
+    // nodes reachable from `roots`, excluding the constant node
+    fn live_count(&self, roots: &[NodeId]) -> usize {
+        let (active, _) = self.get_active_from(roots);
+        active.iter().skip(1).filter(|&&a| a).count()
+    }
+
+    // runs optimization passes, calling `stop` with the old-to-new node map after each pass, so
+    // only a check that needs the current roots pays for resolving them
+    fn run_optimizer<M, F>(
+        &mut self,
+        config: &mut OptimizerConfig<M>,
+        mut stop: F,
+    ) -> OptimizeReport
+    where
+        M: Mergeable<T>,
+        F: FnMut(&Self, &[NodeId]) -> bool,
+    {
+        let mut report = OptimizeReport {
+            nodes_before: self.live_count(&self.roots),
+            ..OptimizeReport::default()
+        };
+
         // merger initialization
         let mut merger = Merger::new(&mut config.merger);
 
@@ -85,7 +308,6 @@ impl<T: Hash + PartialEq> Expression<T> {
 
         // loop through until there's no more nodes to optimize
         let mut i = 0;
-        let mut iter_count = 0;
         let mut iter_end = self.nodes.len();
         while i < self.nodes.len() {
             // optimize the node, possibly creating a new node id
@@ -94,11 +316,23 @@ impl<T: Hash + PartialEq> Expression<T> {
                 Node::Set(_) => NodeId::new(i as u32, false),
                 Node::Union(kids) => {
                     let kids = kids.iter().map(|&k| resolve(k, &remap)).collect();
-                    self.apply_logic_reduction(kids, true, &mut merger, config.merger_depth)
+                    self.apply_logic_reduction(
+                        kids,
+                        true,
+                        &mut merger,
+                        config.merger_depth,
+                        config.factor_intersections,
+                    )
                 }
                 Node::Intersection(kids) => {
                     let kids = kids.iter().map(|&k| resolve(k, &remap)).collect();
-                    self.apply_logic_reduction(kids, false, &mut merger, config.merger_depth)
+                    self.apply_logic_reduction(
+                        kids,
+                        false,
+                        &mut merger,
+                        config.merger_depth,
+                        config.factor_intersections,
+                    )
                 }
             };
 
@@ -114,11 +348,12 @@ impl<T: Hash + PartialEq> Expression<T> {
             // max iterations
             i += 1;
             if i >= iter_end {
-                if config.max_iterations != 0 {
-                    iter_count += 1;
-                    if iter_count >= config.max_iterations {
-                        break;
-                    }
+                report.iterations += 1;
+                if config.max_iterations != 0 && report.iterations >= config.max_iterations {
+                    break;
+                }
+                if stop(self, &remap) {
+                    break;
                 }
                 // resize remap for new nodes
                 iter_end = self.nodes.len();
@@ -126,10 +361,104 @@ impl<T: Hash + PartialEq> Expression<T> {
             }
         }
 
+        // a pass that created no new nodes leaves nothing left to revisit
+        report.fixed_point = i >= self.nodes.len();
+
         // remap roots
         for root in &mut self.roots {
-            *root = resolve(*root, &remap);
+            let new_root = resolve(*root, &remap);
+            report.collapsed_root |= root.idx() != 0 && new_root.idx() == 0;
+            *root = new_root;
+        }
+        report.nodes_after = self.live_count(&self.roots);
+        report
+    }
+
+    /// Finds the AND factors shared by every root.
+    ///
+    /// A root's factors are the children of a top-level Intersection (or the root itself).
+    /// A factor of the first root is reported if every other root has a factor the `merger`
+    /// considers equal to it. This is read-only, and is useful for lifting a shared condition
+    /// (e.g. `& !Banned`) out of a set of rules.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::ExpressionBuilder;
+    ///
+    /// let builder = ExpressionBuilder::<&str>::new();
+    /// let banned = builder.leaf("Banned");
+    /// builder.add_root(builder.leaf("Admin") & !banned);
+    /// builder.add_root((builder.leaf("Editor") | builder.leaf("Viewer")) & !banned);
+    /// let expr = builder.build();
+    ///
+    /// let common = expr.common_root_factors(&mut ());
+    /// assert_eq!(common.len(), 1);
+    /// assert_eq!(expr.to_string(&common[0]), "[Banned]'");
+    /// ```
+    pub fn common_root_factors<M: Mergeable<T>>(&self, merger: &mut M) -> Vec<NodeId> {
+        let mut merger = Merger::new(merger);
+        let Some((&first, rest)) = self.roots.split_first() else {
+            return Vec::new();
+        };
+
+        let factors_of = |root: NodeId| -> Vec<NodeId> {
+            match &self.nodes[root.idx()] {
+                Node::Intersection(kids) if !root.is_neg() => kids.clone(),
+                Node::Union(kids) if root.is_neg() => kids.iter().map(|k| k.not()).collect(), // (A | B)' == A' & B'
+                _ => vec![root],
+            }
+        };
+
+        let mut common = factors_of(first);
+        for &root in rest {
+            let factors = factors_of(root);
+            common.retain(|&c| {
+                factors.iter().any(|&f| {
+                    merger.get_relation(self, c, f, DEFAULT_MERGER_DEPTH) == MergeRelation::EQUAL
+                })
+            });
+            if common.is_empty() {
+                break;
+            }
+        }
+        common
+    }
+
+    /// Finds roots whose result is always contained in another root's.
+    ///
+    /// Returns `(subsumed, subsumer)` pairs of root indices, where the first root implies
+    /// the second according to the merger. Equal roots subsume each other, so both
+    /// directions are reported. The check is read-only and uses the same relation lookups
+    /// as the optimizer, so it can miss implications hidden deeper than the default depth.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::ExpressionBuilder;
+    ///
+    /// let builder = ExpressionBuilder::<&str>::new();
+    /// builder.add_root(builder.leaf("Admin") & builder.leaf("Active"));
+    /// builder.add_root(builder.leaf("Admin"));
+    /// builder.add_root(builder.leaf("Guest"));
+    /// let expr = builder.build();
+    ///
+    /// // "Admin & Active" is redundant next to "Admin"
+    /// assert_eq!(expr.subsumed_roots(&mut ()), vec![(0, 1)]);
+    /// ```
+    pub fn subsumed_roots<M: Mergeable<T>>(&self, merger: &mut M) -> Vec<(usize, usize)> {
+        let mut merger = Merger::new(merger);
+        let mut pairs = Vec::new();
+        for (i, &a) in self.roots.iter().enumerate() {
+            for (j, &b) in self.roots.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+                let relation = merger.get_relation(self, a, b, DEFAULT_MERGER_DEPTH);
+                if relation.contains(MergeRelation::SUBSET) {
+                    pairs.push((i, j));
+                }
+            }
         }
+        pairs
     }
 }
 
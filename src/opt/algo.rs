@@ -1,17 +1,21 @@
-use std::hash::Hash;
+use std::{borrow::Cow, hash::Hash};
 
 use crate::{
     expr::{Expression, Node, NodeId},
     opt::merger::{MergeRelation, MergeResult, Mergeable, Merger},
 };
 
-impl<T: Hash + PartialEq> Expression<T> {
+// consensus elimination is cubic in the number of union children, so larger unions skip it
+const CONSENSUS_MAX_KIDS: usize = 32;
+
+impl<T: Hash + PartialEq, Meta> Expression<T, Meta> {
     pub(super) fn apply_logic_reduction<M: Mergeable<T>>(
         &mut self,
         mut kids: Vec<NodeId>,
         is_union: bool,
         merger: &mut Merger<T, M>,
         merger_depth: usize,
+        factor_intersections: bool,
     ) -> NodeId {
         // De Morgan's
         let should_flip = if is_union {
@@ -30,7 +34,13 @@ impl<T: Hash + PartialEq> Expression<T> {
         if should_flip {
             let flipped_kids = kids.iter().map(|k| k.not()).collect();
             return self
-                .apply_logic_reduction(flipped_kids, !is_union, merger, merger_depth)
+                .apply_logic_reduction(
+                    flipped_kids,
+                    !is_union,
+                    merger,
+                    merger_depth,
+                    factor_intersections,
+                )
                 .not();
         }
 
@@ -56,6 +66,11 @@ impl<T: Hash + PartialEq> Expression<T> {
         }
         kids = flat_kids;
 
+        // exclusive or, (A & B') | (A' & B) == E when A == B, U when A == B'
+        if is_union && let Some(constant) = self.try_xor(&kids, merger, merger_depth) {
+            return constant;
+        }
+
         if kids.len() >= 2 {
             // absorption A & (A & B)' => A & B'
             let mut i = 0;
@@ -176,10 +191,24 @@ impl<T: Hash + PartialEq> Expression<T> {
                                 let neg_b = id_b.is_neg();
 
                                 // get the merged node if it can be merged
-                                let merged = if is_union {
-                                    merger.mergeable.merge_union(a, neg_a, b, neg_b)
-                                } else {
-                                    merger.mergeable.merge_intersection(a, neg_a, b, neg_b)
+                                let merged = match (is_union, neg_a, neg_b) {
+                                    (true, _, _) => {
+                                        merger.mergeable.merge_union(a, neg_a, b, neg_b)
+                                    }
+                                    // A & B' is a difference, A - B
+                                    (false, false, true) => {
+                                        merger.mergeable.merge_difference(a, b).or_else(|| {
+                                            merger.mergeable.merge_intersection(a, neg_a, b, neg_b)
+                                        })
+                                    }
+                                    (false, true, false) => {
+                                        merger.mergeable.merge_difference(b, a).or_else(|| {
+                                            merger.mergeable.merge_intersection(a, neg_a, b, neg_b)
+                                        })
+                                    }
+                                    (false, _, _) => {
+                                        merger.mergeable.merge_intersection(a, neg_a, b, neg_b)
+                                    }
                                 };
                                 if let Some(res) = merged {
                                     // get new node id
@@ -219,9 +248,16 @@ impl<T: Hash + PartialEq> Expression<T> {
                 i += 1;
             }
 
+            // consensus, (A & B) | (A' & C) | (B & C) == (A & B) | (A' & C)
+            if is_union && merger_depth >= 2 && kids.len() <= CONSENSUS_MAX_KIDS {
+                self.remove_consensus_terms(&mut kids, merger, merger_depth);
+            }
+
             // attempt factoring
-            // note: factoring intersections may result in harder evaluations (no early returns in unions), so stick to union factoring
-            if is_union && let Some(factored) = self.try_factoring(&kids) {
+            // note: factoring intersections may result in harder evaluations (no early returns in unions), so it is opt-in
+            if (is_union || factor_intersections)
+                && let Some(factored) = self.try_factoring(&kids, is_union)
+            {
                 return factored;
             }
         }
@@ -234,29 +270,56 @@ impl<T: Hash + PartialEq> Expression<T> {
         }
     }
 
-    // NOTE: only handles unions of intersections/sets
-    fn try_factoring(&mut self, kids: &[NodeId]) -> Option<NodeId> {
+    // recognizes the shape built by `Expression::xor`, resolving it with a single relation
+    // lookup between the operands instead of comparing the two branches
+    fn try_xor<M: Mergeable<T>>(
+        &self,
+        kids: &[NodeId],
+        merger: &mut Merger<T, M>,
+        merger_depth: usize,
+    ) -> Option<NodeId> {
+        let &[left, right] = kids else {
+            return None;
+        };
+        if left.is_neg() || right.is_neg() {
+            return None;
+        }
+        let (Node::Intersection(l), Node::Intersection(r)) =
+            (&self.nodes[left.idx()], &self.nodes[right.idx()])
+        else {
+            return None;
+        };
+        let (&[a, not_b], &[c, d]) = (l.as_slice(), r.as_slice()) else {
+            return None;
+        };
+
+        // the right branch must be the left one with every term negated
+        let (not_a, b) = (a.not(), not_b.not());
+        if !((c == not_a && d == b) || (c == b && d == not_a)) {
+            return None;
+        }
+
+        let rel = merger.get_relation(self, a, b, merger_depth);
+        if rel == MergeRelation::EQUAL {
+            Some(NodeId::EMPTY) // A ^ A == E
+        } else if rel == MergeRelation::COMPLEMENTARY {
+            Some(NodeId::UNIVERSAL) // A ^ A' == U
+        } else {
+            None
+        }
+    }
+
+    // (A & B) | (A & C) => A & (B | C) in unions, or the dual (A | B) & (A | C) => A | (B & C)
+    fn try_factoring(&mut self, kids: &[NodeId], is_union: bool) -> Option<NodeId> {
         // loops through each child
         for i in 0..kids.len() {
-            let owned_i;
-            let kids_i = match &self.nodes[kids[i].idx()] {
-                Node::Intersection(children) if !kids[i].is_neg() => children,
-                Node::Union(children) if kids[i].is_neg() => {
-                    owned_i = children.iter().map(|id| id.not()).collect();
-                    &owned_i
-                }
-                _ => continue, // ignore Node::Set(), handled in Merger absorption
+            let Some(kids_i) = inner_terms(&self.nodes, kids[i], is_union) else {
+                continue;
             };
 
             for j in (i + 1)..kids.len() {
-                let owned_j;
-                let kids_j = match &self.nodes[kids[j].idx()] {
-                    Node::Intersection(children) if !kids[j].is_neg() => children,
-                    Node::Union(children) if kids[j].is_neg() => {
-                        owned_j = children.iter().map(|id| id.not()).collect();
-                        &owned_j
-                    }
-                    _ => continue, // ignore Node::Set(), handled in Merger absorption
+                let Some(kids_j) = inner_terms(&self.nodes, kids[j], is_union) else {
+                    continue;
                 };
 
                 // collect common terms
@@ -279,26 +342,18 @@ impl<T: Hash + PartialEq> Expression<T> {
                 if !common.is_empty() {
                     // TODO: faster check because they SHOULD? be sorted already
                     // residuals
-                    let mut res_i = kids_i.clone();
+                    let mut res_i = kids_i.into_owned();
                     res_i.retain(|x| !common.contains(x));
-                    let mut res_j = kids_j.clone();
+                    let mut res_j = kids_j.into_owned();
                     res_j.retain(|x| !common.contains(x));
 
-                    // allocate residuals
-                    let res_id_i = if res_i.is_empty() {
-                        NodeId::UNIVERSAL
-                    } else {
-                        self.intersection(res_i)
-                    };
-                    let res_id_j = if res_j.is_empty() {
-                        NodeId::UNIVERSAL
-                    } else {
-                        self.intersection(res_j)
-                    };
+                    // allocate residuals, an empty inner group is its identity
+                    let res_id_i = self.inner_group(res_i, is_union);
+                    let res_id_j = self.inner_group(res_j, is_union);
 
-                    let common_id = self.intersection(common);
-                    let residuals_id = self.union(vec![res_id_i, res_id_j]);
-                    let new_node = self.intersection(vec![common_id, residuals_id]);
+                    let common_id = self.inner_group(common, is_union);
+                    let residuals_id = self.inner_group(vec![res_id_i, res_id_j], !is_union);
+                    let new_node = self.inner_group(vec![common_id, residuals_id], is_union);
 
                     // create the old list with the new node made from two nodes
                     let mut new_kids = Vec::with_capacity(kids.len() - 1);
@@ -308,10 +363,95 @@ impl<T: Hash + PartialEq> Expression<T> {
                             new_kids.push(id);
                         }
                     }
-                    return Some(self.union(new_kids));
+                    return Some(self.inner_group(new_kids, !is_union));
                 }
             }
         }
         None
     }
+
+    // drops every child of a union that implies the consensus of two others
+    fn remove_consensus_terms<M: Mergeable<T>>(
+        &self,
+        kids: &mut Vec<NodeId>,
+        merger: &mut Merger<T, M>,
+        merger_depth: usize,
+    ) {
+        let terms_of =
+            |id: NodeId| inner_terms(&self.nodes, id, true).unwrap_or(Cow::Owned(vec![id]));
+
+        let mut i = 0;
+        while i < kids.len() {
+            let mut j = i + 1;
+            while j < kids.len() {
+                let (id_i, id_j) = (kids[i], kids[j]);
+                let (terms_i, terms_j) = (terms_of(id_i), terms_of(id_j));
+
+                // resolve on the first complementary pair, (A & B) | (A' & C) covers (B & C)
+                let opposed = terms_i.iter().find_map(|&a| {
+                    terms_j
+                        .iter()
+                        .find(|&&b| {
+                            merger.get_relation(self, a, b, merger_depth)
+                                == MergeRelation::COMPLEMENTARY
+                        })
+                        .map(|&b| (a, b))
+                });
+                let Some((a, b)) = opposed else {
+                    j += 1;
+                    continue;
+                };
+                let consensus: Vec<NodeId> = terms_i
+                    .iter()
+                    .filter(|&&t| t != a)
+                    .chain(terms_j.iter().filter(|&&t| t != b))
+                    .copied()
+                    .collect();
+
+                // any other child inside the consensus is redundant
+                let before = kids.len();
+                kids.retain(|&k| {
+                    if k == id_i || k == id_j {
+                        return true;
+                    }
+                    let terms_k = terms_of(k);
+                    !consensus.iter().all(|&c| {
+                        terms_k
+                            .iter()
+                            .any(|&t| merger.get_relation(self, t, c, merger_depth).is_subset())
+                    })
+                });
+                if kids.len() != before {
+                    // removals may shift i and j
+                    i = kids.iter().position(|&k| k == id_i).unwrap();
+                    j = kids.iter().position(|&k| k == id_j).unwrap();
+                }
+                j += 1;
+            }
+            i += 1;
+        }
+    }
+
+    // the group nested inside an `is_union` parent: an intersection in a union, and vice versa
+    fn inner_group(&mut self, kids: Vec<NodeId>, is_union: bool) -> NodeId {
+        if is_union {
+            self.intersection(kids)
+        } else {
+            self.union(kids)
+        }
+    }
+}
+
+// the terms of a group that can be factored inside an `is_union` parent, which is the opposite
+// kind of group, either directly or negated through De Morgan's
+fn inner_terms<T>(nodes: &[Node<T>], id: NodeId, is_union: bool) -> Option<Cow<'_, [NodeId]>> {
+    match (&nodes[id.idx()], is_union) {
+        (Node::Intersection(children), true) | (Node::Union(children), false) if !id.is_neg() => {
+            Some(Cow::Borrowed(children))
+        }
+        (Node::Union(children), true) | (Node::Intersection(children), false) if id.is_neg() => {
+            Some(Cow::Owned(children.iter().map(|id| id.not()).collect()))
+        }
+        _ => None, // ignore Node::Set(), handled in Merger absorption
+    }
 }
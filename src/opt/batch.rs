@@ -0,0 +1,93 @@
+use std::{hash::Hash, mem, ops::Range};
+
+use crate::{
+    expr::{Expression, NodeId},
+    opt::{Mergeable, OptimizerConfig},
+};
+
+/// A handle to one expression's roots inside a [`BatchOptimizer`] pool.
+///
+/// Handles index the pool's root list rather than its nodes, so they stay valid through
+/// [`BatchOptimizer::optimize`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BatchHandle(Range<usize>);
+
+/// Optimizes many expressions into one shared, deduplicated node pool.
+///
+/// Every added expression is interned into the same [`Expression`], so structure shared
+/// between them (a common `& !Banned` suffix, the same leaves) is stored once for the whole
+/// batch. Optimization then runs once over the pool instead of once per expression.
+///
+/// # Example
+/// ```rust
+/// use logify::{ExpressionBuilder, opt::{BatchOptimizer, OptimizerConfig}};
+///
+/// let mut batch = BatchOptimizer::new(OptimizerConfig::default());
+/// let handles: Vec<_> = ["Admin", "Editor", "Viewer"]
+///     .into_iter()
+///     .map(|role| {
+///         let builder = ExpressionBuilder::<&str>::new();
+///         let role = builder.leaf(role);
+///         builder.add_root((role | (role & builder.leaf("Owner"))) & !builder.leaf("Banned"));
+///         batch.add(builder.build())
+///     })
+///     .collect();
+///
+/// batch.optimize();
+///
+/// let pool = batch.pool();
+/// let editor = batch.roots(&handles[1])[0];
+/// assert_eq!(pool.to_string(&editor), "([Banned]' & [Editor])");
+/// // Empty, Banned, and one leaf and AND per role
+/// assert_eq!(pool.node_count(), 2 + 3 * 2);
+/// ```
+pub struct BatchOptimizer<T, M> {
+    pool: Expression<T>,
+    config: OptimizerConfig<M>,
+}
+
+impl<T: Hash + PartialEq, M: Mergeable<T>> BatchOptimizer<T, M> {
+    /// Creates an empty batch that optimizes with `config`.
+    pub fn new(config: OptimizerConfig<M>) -> Self {
+        Self {
+            pool: Expression::new(),
+            config,
+        }
+    }
+
+    /// Moves an expression into the pool, returning a handle to its roots.
+    ///
+    /// Only live nodes are copied. The expression is not optimized until
+    /// [`optimize`](Self::optimize) is called.
+    pub fn add(&mut self, expr: Expression<T>) -> BatchHandle {
+        let start = self.pool.root_count();
+        self.pool.extend(expr);
+        BatchHandle(start..self.pool.root_count())
+    }
+
+    /// Optimizes the whole pool, then drops the nodes left dead by optimization.
+    ///
+    /// Handles remain valid, but any [`NodeId`] read from the pool beforehand does not.
+    pub fn optimize(&mut self) {
+        self.pool.optimize(&mut self.config);
+        self.pool = mem::take(&mut self.pool).prune::<()>();
+    }
+
+    /// Returns the current roots of the expression behind `handle`.
+    ///
+    /// # Panics
+    /// Panics if `handle` came from a different batch.
+    pub fn roots(&self, handle: &BatchHandle) -> &[NodeId] {
+        &self.pool.roots[handle.0.clone()]
+    }
+
+    /// Returns the shared pool, for evaluation or inspection.
+    pub fn pool(&self) -> &Expression<T> {
+        &self.pool
+    }
+
+    /// Consumes the batch, returning the shared pool.
+    pub fn into_pool(self) -> Expression<T> {
+        self.pool
+    }
+}
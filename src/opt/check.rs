@@ -0,0 +1,93 @@
+use crate::opt::merger::{Mergeable, SetRelation};
+
+/// A violation of the [`Mergeable`] relation rules, found by [`check_merger_consistency`].
+///
+/// Indices refer to positions in the `samples` slice.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Inconsistency {
+    /// A term was related to itself as something other than `Equal`, `Subset`, `Superset`,
+    /// or `Trivial` (e.g. disjoint from itself).
+    Reflexive { index: usize, relation: SetRelation },
+    /// The relation of `(a, b)` does not mirror the relation of `(b, a)`.
+    ///
+    /// For example, `Subset` must be answered with `Superset`, and `Disjoint` with `Disjoint`.
+    /// A missing inverse case (`Trivial` in one direction) is reported here too.
+    Asymmetric {
+        a: usize,
+        b: usize,
+        forward: SetRelation,
+        backward: SetRelation,
+    },
+}
+
+/// Tests a [`Mergeable`] implementation for self-contradicting relations.
+///
+/// Every pair of `samples` is checked in both directions, along with every sample against
+/// itself. An inconsistent merger silently produces wrong optimizations, so this is
+/// intended for use in tests.
+///
+/// # Example
+/// ```rust
+/// use logify::opt::{Inconsistency, Mergeable, SetRelation, check_merger_consistency};
+///
+/// struct GeoMerger;
+/// impl Mergeable<&str> for GeoMerger {
+///     fn get_relation(&mut self, a: &&str, b: &&str) -> SetRelation {
+///         match (*a, *b) {
+///             ("Paris", "France") => SetRelation::Subset,
+///             // forgot ("France", "Paris") => SetRelation::Superset
+///             _ => SetRelation::Trivial,
+///         }
+///     }
+/// }
+///
+/// let issues = check_merger_consistency(&mut GeoMerger, &["Paris", "France"]);
+/// assert_eq!(
+///     issues,
+///     vec![Inconsistency::Asymmetric {
+///         a: 0,
+///         b: 1,
+///         forward: SetRelation::Subset,
+///         backward: SetRelation::Trivial,
+///     }]
+/// );
+/// ```
+pub fn check_merger_consistency<T, M: Mergeable<T>>(
+    merger: &mut M,
+    samples: &[T],
+) -> Vec<Inconsistency> {
+    let mut issues = Vec::new();
+    for (i, a) in samples.iter().enumerate() {
+        // a term can't be disjoint from or cover the universe with itself
+        let relation = merger.get_relation(a, a);
+        if matches!(
+            relation,
+            SetRelation::Disjoint | SetRelation::Cover | SetRelation::Complementary
+        ) {
+            issues.push(Inconsistency::Reflexive { index: i, relation });
+        }
+
+        for (j, b) in samples.iter().enumerate().skip(i + 1) {
+            let forward = merger.get_relation(a, b);
+            let backward = merger.get_relation(b, a);
+            if mirror(forward) != backward {
+                issues.push(Inconsistency::Asymmetric {
+                    a: i,
+                    b: j,
+                    forward,
+                    backward,
+                });
+            }
+        }
+    }
+    issues
+}
+
+// the relation (b, a) must have when (a, b) has the given relation
+fn mirror(relation: SetRelation) -> SetRelation {
+    match relation {
+        SetRelation::Subset => SetRelation::Superset,
+        SetRelation::Superset => SetRelation::Subset,
+        other => other,
+    }
+}
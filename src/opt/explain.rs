@@ -0,0 +1,161 @@
+use std::hash::Hash;
+
+use crate::{
+    expr::{Expression, Node, NodeId},
+    opt::{
+        DEFAULT_MERGER_DEPTH,
+        merger::{MergeResult, Mergeable, Merger},
+    },
+};
+
+/// Why two factors of an AND can never hold together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContradictionKind {
+    /// The merger reported the two terms as disjoint (or one is the complement of the other).
+    Disjoint,
+    /// [`Mergeable::merge_intersection`] combined the two sets into Empty.
+    MergedEmpty,
+}
+
+/// A pair of conflicting terms inside an AND, found by [`Expression::explain_empty`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Contradiction {
+    /// The first conflicting factor.
+    pub a: NodeId,
+    /// The second conflicting factor.
+    pub b: NodeId,
+    /// How the conflict was detected.
+    pub kind: ContradictionKind,
+}
+
+impl<T: Hash + PartialEq, Meta> Expression<T, Meta> {
+    /// Explains why a root can never match, by finding a conflicting pair of terms.
+    ///
+    /// Call this on the expression *before* optimizing, as an optimized root has already
+    /// collapsed to `EMPTY` and lost its terms. Nested ANDs are flattened, so the conflicting
+    /// pair may come from different levels. An OR is only explained if every branch conflicts,
+    /// in which case the first branch's conflict is returned.
+    ///
+    /// Returns `None` if no contradiction is found. Conflicts that require distributing an
+    /// AND over an OR, like `A & (B | C)` with `A` disjoint from both, are not detected.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{
+    ///     ExpressionBuilder,
+    ///     opt::{ContradictionKind, Mergeable, SetRelation},
+    /// };
+    ///
+    /// struct GeoMerger;
+    /// impl Mergeable<&str> for GeoMerger {
+    ///     fn get_relation(&mut self, a: &&str, b: &&str) -> SetRelation {
+    ///         match (*a, *b) {
+    ///             ("Texas", "France") | ("France", "Texas") => SetRelation::Disjoint,
+    ///             _ => SetRelation::Trivial,
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let builder = ExpressionBuilder::new();
+    /// builder.add_root(builder.leaf("Texas") & (builder.leaf("Open") & builder.leaf("France")));
+    /// let expr = builder.build();
+    ///
+    /// let root = *expr.roots().next().unwrap();
+    /// let why = expr.explain_empty(root, &mut GeoMerger).unwrap();
+    /// assert_eq!(why.kind, ContradictionKind::Disjoint);
+    /// assert_eq!(expr.to_string(&why.a), "[Texas]");
+    /// assert_eq!(expr.to_string(&why.b), "[France]");
+    /// ```
+    pub fn explain_empty<M: Mergeable<T>>(
+        &self,
+        root: NodeId,
+        merger: &mut M,
+    ) -> Option<Contradiction> {
+        let mut merger = Merger::new(merger);
+        let mut memo = vec![None; self.nodes.len() * 2];
+        self.find_contradiction(root, &mut merger, &mut memo)
+    }
+
+    fn find_contradiction<M: Mergeable<T>>(
+        &self,
+        id: NodeId,
+        merger: &mut Merger<T, M>,
+        memo: &mut [Option<Option<Contradiction>>],
+    ) -> Option<Contradiction> {
+        if let Some(found) = memo[id.raw() as usize] {
+            return found;
+        }
+        let found = self.search_contradiction(id, merger, memo);
+        memo[id.raw() as usize] = Some(found);
+        found
+    }
+
+    fn search_contradiction<M: Mergeable<T>>(
+        &self,
+        id: NodeId,
+        merger: &mut Merger<T, M>,
+        memo: &mut [Option<Option<Contradiction>>],
+    ) -> Option<Contradiction> {
+        let (kids, is_union) = match &self.nodes[id.idx()] {
+            Node::Union(kids) => (kids, !id.is_neg()),
+            Node::Intersection(kids) => (kids, id.is_neg()),
+            _ => return None,
+        };
+        // (A | B)' == A' & B', (A & B)' == A' | B'
+        let effective = |k: NodeId| if id.is_neg() { k.not() } else { k };
+
+        if is_union {
+            let mut first = None;
+            for &k in kids {
+                let found = self.find_contradiction(effective(k), merger, memo)?;
+                first.get_or_insert(found);
+            }
+            return first;
+        }
+
+        // flatten nested ANDs into one list of factors
+        let mut factors = Vec::new();
+        let mut stack: Vec<NodeId> = kids.iter().rev().map(|&k| effective(k)).collect();
+        while let Some(k) = stack.pop() {
+            match &self.nodes[k.idx()] {
+                Node::Intersection(g) if !k.is_neg() => stack.extend(g.iter().rev()),
+                Node::Union(g) if k.is_neg() => stack.extend(g.iter().rev().map(|g| g.not())),
+                _ => factors.push(k),
+            }
+        }
+
+        // look for a conflicting pair
+        for (i, &a) in factors.iter().enumerate() {
+            for &b in &factors[i + 1..] {
+                let rel = merger.get_relation(self, a, b, DEFAULT_MERGER_DEPTH);
+                if rel.is_disjoint() {
+                    return Some(Contradiction {
+                        a,
+                        b,
+                        kind: ContradictionKind::Disjoint,
+                    });
+                }
+                if let (Node::Set(set_a), Node::Set(set_b)) =
+                    (&self.nodes[a.idx()], &self.nodes[b.idx()])
+                {
+                    let merged =
+                        merger
+                            .mergeable
+                            .merge_intersection(set_a, a.is_neg(), set_b, b.is_neg());
+                    if matches!(merged, Some(MergeResult::Empty)) {
+                        return Some(Contradiction {
+                            a,
+                            b,
+                            kind: ContradictionKind::MergedEmpty,
+                        });
+                    }
+                }
+            }
+        }
+
+        // a single unsatisfiable factor empties the whole AND
+        factors
+            .into_iter()
+            .find_map(|f| self.find_contradiction(f, merger, memo))
+    }
+}
@@ -1,4 +1,4 @@
-use std::marker::PhantomData;
+use std::{cell::RefCell, marker::PhantomData};
 
 use hashbrown::HashMap;
 
@@ -62,6 +62,7 @@ impl MergeRelation {
 /// One or more results can be left out of the return. However, it may prevent optimizations.
 ///
 /// **Subet / Superset** depend on each other, so returning only one may prevent optimizations for the other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SetRelation {
     /// No known relationship.
     Trivial,
@@ -200,10 +201,207 @@ pub trait Mergeable<T> {
     ) -> Option<MergeResult<T>> {
         None
     }
+
+    /// Attempts to combine two sets using a Difference (`include AND NOT exclude`) operation.
+    ///
+    /// Called before [`merge_intersection`](Self::merge_intersection) when exactly one side of
+    /// an intersection is negated, so domains with a natural difference never see the complement.
+    /// Returning `None` falls back to `merge_intersection`.
+    ///
+    /// # Example
+    /// Interval trimming: `[0, 10)` AND NOT `[5, 15)` becomes `[0, 5)`.
+    /// ```rust
+    /// use std::ops::Range;
+    /// use logify::{Expression, opt::{MergeResult, Mergeable, OptimizerConfig}};
+    ///
+    /// struct Intervals;
+    ///
+    /// impl Mergeable<Range<u32>> for Intervals {
+    ///     fn merge_difference(
+    ///         &mut self,
+    ///         include: &Range<u32>,
+    ///         exclude: &Range<u32>,
+    ///     ) -> Option<MergeResult<Range<u32>>> {
+    ///         let head = include.start..include.end.min(exclude.start);
+    ///         let tail = include.start.max(exclude.end)..include.end;
+    ///         match (head.is_empty(), tail.is_empty()) {
+    ///             (true, true) => Some(MergeResult::Empty),
+    ///             (false, true) => Some(head.into()),
+    ///             (true, false) => Some(tail.into()),
+    ///             (false, false) => None, // a hole in the middle is not a single interval
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let mut expr = Expression::new();
+    /// let a = expr.set(0..10);
+    /// let b = expr.set(5..15);
+    /// let root = expr.intersection([a, b.negated()]);
+    /// expr.add_root(root);
+    ///
+    /// let mut config = OptimizerConfig {
+    ///     merger: Intervals,
+    ///     merger_depth: 1,
+    ///     max_iterations: 0,
+    ///     factor_intersections: false,
+    /// };
+    /// expr.optimize(&mut config);
+    /// assert_eq!(expr.roots_owned(), vec![expr.set(0..5)]);
+    /// ```
+    fn merge_difference(&mut self, _include: &T, _exclude: &T) -> Option<MergeResult<T>> {
+        None
+    }
 }
 
 impl<T> Mergeable<T> for () {}
 
+/// A fallible mirror of [`Mergeable`], for domain logic that can fail.
+///
+/// Use this when answering a relation or merge needs something that may error, such as a
+/// lookup against an external service. Pass it to
+/// [`Expression::try_optimize`](crate::expr::Expression::try_optimize), which stops at the
+/// first error and returns it. The same rules as [`Mergeable`] apply.
+///
+/// # Example
+/// ```rust
+/// use logify::{ExpressionBuilder, opt::{OptimizerConfig, SetRelation, TryMergeable}};
+///
+/// struct Lookup;
+///
+/// impl TryMergeable<&str> for Lookup {
+///     type Error = String;
+///
+///     fn try_get_relation(&mut self, a: &&str, b: &&str) -> Result<SetRelation, String> {
+///         match (*a, *b) {
+///             ("Admin", "User") => Ok(SetRelation::Subset),
+///             ("User", "Admin") => Ok(SetRelation::Superset),
+///             _ => Err(format!("no answer for {} and {}", a, b)),
+///         }
+///     }
+/// }
+///
+/// let builder = ExpressionBuilder::new();
+/// builder.add_root(builder.leaf("Admin") & builder.leaf("User"));
+/// let mut expr = builder.build();
+///
+/// let mut config = OptimizerConfig {
+///     merger: Lookup,
+///     merger_depth: 2,
+///     max_iterations: 0,
+///     factor_intersections: false,
+/// };
+/// assert!(expr.try_optimize(&mut config).is_ok());
+/// assert_eq!(expr.to_string(&expr.roots_owned()[0]), "[Admin]");
+///
+/// let builder = ExpressionBuilder::new();
+/// builder.add_root(builder.leaf("Admin") & builder.leaf("Guest"));
+/// let mut expr = builder.build();
+/// assert!(expr.try_optimize(&mut config).is_err());
+/// ```
+pub trait TryMergeable<T> {
+    /// The error returned when a lookup fails.
+    type Error;
+
+    /// Determines the relationship between two sets.
+    ///
+    /// See [`Mergeable::get_relation`].
+    fn try_get_relation(&mut self, _a: &T, _b: &T) -> Result<SetRelation, Self::Error> {
+        Ok(SetRelation::Trivial)
+    }
+
+    /// Attempts to combine two sets using a Union (OR) operation.
+    ///
+    /// See [`Mergeable::merge_union`].
+    fn try_merge_union(
+        &mut self,
+        _a: &T,
+        _a_neg: bool,
+        _b: &T,
+        _b_neg: bool,
+    ) -> Result<Option<MergeResult<T>>, Self::Error> {
+        Ok(None)
+    }
+
+    /// Attempts to combine two sets using an Intersection (AND) operation.
+    ///
+    /// See [`Mergeable::merge_intersection`].
+    fn try_merge_intersection(
+        &mut self,
+        _a: &T,
+        _a_neg: bool,
+        _b: &T,
+        _b_neg: bool,
+    ) -> Result<Option<MergeResult<T>>, Self::Error> {
+        Ok(None)
+    }
+
+    /// Attempts to combine two sets using a Difference (`include AND NOT exclude`) operation.
+    ///
+    /// See [`Mergeable::merge_difference`].
+    fn try_merge_difference(
+        &mut self,
+        _include: &T,
+        _exclude: &T,
+    ) -> Result<Option<MergeResult<T>>, Self::Error> {
+        Ok(None)
+    }
+}
+
+// runs a `TryMergeable` as a `Mergeable`, keeping the first error and answering nothing after it
+pub(crate) struct Fallible<'a, M, E> {
+    pub inner: &'a mut M,
+    pub error: &'a RefCell<Option<E>>,
+}
+
+impl<M, E> Fallible<'_, M, E> {
+    fn record<R>(&mut self, result: Result<R, E>, fallback: R) -> R {
+        result.unwrap_or_else(|e| {
+            *self.error.borrow_mut() = Some(e);
+            fallback
+        })
+    }
+}
+
+impl<T, M: TryMergeable<T>> Mergeable<T> for Fallible<'_, M, M::Error> {
+    fn get_relation(&mut self, a: &T, b: &T) -> SetRelation {
+        if self.error.borrow().is_some() {
+            return SetRelation::Trivial;
+        }
+        let result = self.inner.try_get_relation(a, b);
+        self.record(result, SetRelation::Trivial)
+    }
+
+    fn merge_union(&mut self, a: &T, a_neg: bool, b: &T, b_neg: bool) -> Option<MergeResult<T>> {
+        if self.error.borrow().is_some() {
+            return None;
+        }
+        let result = self.inner.try_merge_union(a, a_neg, b, b_neg);
+        self.record(result, None)
+    }
+
+    fn merge_intersection(
+        &mut self,
+        a: &T,
+        a_neg: bool,
+        b: &T,
+        b_neg: bool,
+    ) -> Option<MergeResult<T>> {
+        if self.error.borrow().is_some() {
+            return None;
+        }
+        let result = self.inner.try_merge_intersection(a, a_neg, b, b_neg);
+        self.record(result, None)
+    }
+
+    fn merge_difference(&mut self, include: &T, exclude: &T) -> Option<MergeResult<T>> {
+        if self.error.borrow().is_some() {
+            return None;
+        }
+        let result = self.inner.try_merge_difference(include, exclude);
+        self.record(result, None)
+    }
+}
+
 pub(crate) struct Merger<'a, T, M: Mergeable<T>> {
     pub mergeable: &'a mut M,
     cache: HashMap<(usize, usize), (MergeRelation, usize)>,
@@ -219,9 +417,9 @@ impl<'a, T, M: Mergeable<T>> Merger<'a, T, M> {
         }
     }
 
-    pub(crate) fn get_relation(
+    pub(crate) fn get_relation<Meta>(
         &mut self,
-        expr: &Expression<T>,
+        expr: &Expression<T, Meta>,
         a: NodeId,
         b: NodeId,
         depth: usize,
@@ -237,9 +435,9 @@ impl<'a, T, M: Mergeable<T>> Merger<'a, T, M> {
         self.get_relation_recursive(expr, a, b, depth)
     }
 
-    fn get_relation_recursive(
+    fn get_relation_recursive<Meta>(
         &mut self,
-        expr: &Expression<T>,
+        expr: &Expression<T, Meta>,
         a: NodeId,
         b: NodeId,
         depth: usize,
@@ -289,14 +487,17 @@ impl<'a, T, M: Mergeable<T>> Merger<'a, T, M> {
                 self.mergeable.get_relation(set_min, set_max).into()
             }
             // Set and Group
+            // the relation is between the positive nodes, so the set is passed unnegated
             (Node::Set(_), Node::Union(kids_b)) | (Node::Set(_), Node::Intersection(kids_b)) => {
                 let is_union = matches!(node_max, Node::Union(_));
-                self.get_groups_relation(expr, &[min], is_union, kids_b, is_union, depth - 1)
+                let set = NodeId::new(min.idx() as u32, false);
+                self.get_groups_relation(expr, &[set], is_union, kids_b, is_union, depth - 1)
             }
             // Group and Set
             (Node::Union(kids_a), Node::Set(_)) | (Node::Intersection(kids_a), Node::Set(_)) => {
                 let is_union = matches!(node_min, Node::Union(_));
-                self.get_groups_relation(expr, kids_a, is_union, &[max], is_union, depth - 1)
+                let set = NodeId::new(max.idx() as u32, false);
+                self.get_groups_relation(expr, kids_a, is_union, &[set], is_union, depth - 1)
             }
             // Group and Group
             (Node::Union(kids_a), Node::Union(kids_b))
@@ -398,9 +599,9 @@ impl<'a, T, M: Mergeable<T>> Merger<'a, T, M> {
         result
     }
 
-    fn get_groups_relation(
+    fn get_groups_relation<Meta>(
         &mut self,
-        expr: &Expression<T>,
+        expr: &Expression<T, Meta>,
         kids_a: &[NodeId],
         is_union_a: bool,
         kids_b: &[NodeId],
@@ -501,7 +702,7 @@ impl<'a, T, M: Mergeable<T>> Merger<'a, T, M> {
                 kids_b.iter().all(|&b| {
                     kids_a
                         .iter()
-                        .any(|&a| self.get_relation_recursive(expr, a, b, depth).is_superset())
+                        .any(|&a| self.get_relation_recursive(expr, a, b, depth).is_subset())
                 })
             }
         };
@@ -518,7 +719,7 @@ impl<'a, T, M: Mergeable<T>> Merger<'a, T, M> {
                 kids_b.iter().all(|&b| {
                     kids_a
                         .iter()
-                        .any(|&a| self.get_relation_recursive(expr, a, b, depth).is_subset())
+                        .any(|&a| self.get_relation_recursive(expr, a, b, depth).is_superset())
                 })
             }
             (true, false) =>
@@ -0,0 +1,660 @@
+use std::{
+    fmt::{self, Display},
+    hash::Hash,
+    mem,
+    str::FromStr,
+};
+
+use crate::{
+    builder::{ExpressionBuilder, NodeHandle},
+    expr::{Expression, Node, NodeId},
+};
+
+/// The operator tokens recognized by [`Expression::parse_with`].
+///
+/// Each operator may be spelled several ways. Symbolic tokens (`&`, `∧`) can touch their
+/// operands, e.g. `A&B`. Keyword tokens (`AND`) must stand apart from identifiers, so a
+/// leaf named `ANDROID` is never split.
+///
+/// # Example
+/// ```rust
+/// use logify::{Expression, parse::ParserSyntax};
+///
+/// let syntax = ParserSyntax {
+///     and: vec!["AND".into(), "∧".into()],
+///     or: vec!["OR".into(), "∨".into()],
+///     not: vec!["NOT".into(), "¬".into()],
+/// };
+///
+/// let expr: Expression<String> = Expression::parse_with("ANDROID AND NOT (iOS ∨ Web)", &syntax).unwrap();
+/// let root = expr.roots().next().unwrap();
+/// assert_eq!(expr.to_string(root), "([ANDROID] & ([iOS] | [Web])')");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParserSyntax {
+    /// Tokens for Intersection (`A AND B`).
+    pub and: Vec<String>,
+    /// Tokens for Union (`A OR B`).
+    pub or: Vec<String>,
+    /// Tokens for the prefix complement (`NOT A`).
+    pub not: Vec<String>,
+}
+
+impl Default for ParserSyntax {
+    /// The standard `&`, `|`, and `!` operators.
+    fn default() -> Self {
+        Self {
+            and: vec!["&".into()],
+            or: vec!["|".into()],
+            not: vec!["!".into()],
+        }
+    }
+}
+
+/// The reason a string failed to parse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// A token appeared where it is not allowed.
+    UnexpectedToken,
+    /// The input ended while an operand was still expected.
+    UnexpectedEnd,
+    /// An opening parenthesis was never closed.
+    UnclosedParen,
+    /// A leaf identifier was rejected by the term's `FromStr`.
+    InvalidLeaf,
+    /// A quoted leaf was never closed.
+    UnclosedQuote,
+}
+
+/// An error produced while parsing an expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseError {
+    /// The byte offset into the input where the problem was found.
+    pub offset: usize,
+    /// What went wrong.
+    pub kind: ParseErrorKind,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self.kind {
+            ParseErrorKind::UnexpectedToken => "unexpected token",
+            ParseErrorKind::UnexpectedEnd => "unexpected end of input",
+            ParseErrorKind::UnclosedParen => "unclosed parenthesis",
+            ParseErrorKind::InvalidLeaf => "invalid leaf",
+            ParseErrorKind::UnclosedQuote => "unclosed quote",
+        };
+        write!(f, "{} at byte {}", msg, self.offset)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Token<'a> {
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+    Leaf(&'a str),
+    // the text between the quotes, still escaped
+    Quoted(&'a str),
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Splits the input into `(offset, token)` pairs.
+fn tokenize<'a>(
+    input: &'a str,
+    syntax: &ParserSyntax,
+) -> Result<Vec<(usize, Token<'a>)>, ParseError> {
+    // longest tokens first, so `&&` wins over `&`
+    let mut ops: Vec<(&str, Token)> = Vec::new();
+    ops.extend(syntax.and.iter().map(|s| (s.as_str(), Token::And)));
+    ops.extend(syntax.or.iter().map(|s| (s.as_str(), Token::Or)));
+    ops.extend(syntax.not.iter().map(|s| (s.as_str(), Token::Not)));
+    ops.retain(|(s, _)| !s.is_empty());
+    ops.sort_by_key(|(s, _)| std::cmp::Reverse(s.len()));
+
+    // keywords only match on a word boundary, symbols match anywhere
+    let match_op = |pos: usize, in_word: bool| {
+        let rest = &input[pos..];
+        ops.iter().find_map(|&(s, tok)| {
+            let keyword = s.ends_with(is_word_char);
+            if in_word && keyword || !rest.starts_with(s) {
+                return None;
+            }
+            let after = rest[s.len()..].chars().next();
+            if keyword && after.is_some_and(is_word_char) {
+                return None;
+            }
+            Some((s.len(), tok))
+        })
+    };
+
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    while let Some(c) = input[pos..].chars().next() {
+        if c.is_whitespace() {
+            pos += c.len_utf8();
+            continue;
+        }
+        if c == '(' || c == ')' {
+            let tok = if c == '(' { Token::Open } else { Token::Close };
+            tokens.push((pos, tok));
+            pos += 1;
+            continue;
+        }
+        if let Some((len, tok)) = match_op(pos, false) {
+            tokens.push((pos, tok));
+            pos += len;
+            continue;
+        }
+
+        // quoted leaf, runs until an unescaped quote
+        if c == '"' {
+            let start = pos;
+            let mut chars = input[pos + 1..].char_indices();
+            let close = loop {
+                match chars.next() {
+                    Some((_, '\\')) => {
+                        chars.next();
+                    }
+                    Some((i, '"')) => break pos + 1 + i,
+                    Some(_) => {}
+                    None => {
+                        return Err(ParseError {
+                            offset: start,
+                            kind: ParseErrorKind::UnclosedQuote,
+                        });
+                    }
+                }
+            };
+            tokens.push((start, Token::Quoted(&input[start + 1..close])));
+            pos = close + 1;
+            continue;
+        }
+
+        // leaf, runs until whitespace, a paren, or a symbolic operator
+        let start = pos;
+        pos += c.len_utf8();
+        while let Some(c) = input[pos..].chars().next() {
+            if c.is_whitespace() || c == '(' || c == ')' || match_op(pos, true).is_some() {
+                break;
+            }
+            pos += c.len_utf8();
+        }
+        tokens.push((start, Token::Leaf(&input[start..pos])));
+    }
+    Ok(tokens)
+}
+
+// undoes the escaping inside a quoted leaf
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        out.extend(if c == '\\' { chars.next() } else { Some(c) });
+    }
+    out
+}
+
+/// Where parsed nodes are created, either an Expression or a builder.
+trait Target<T> {
+    type Id;
+    fn leaf(&mut self, value: T) -> Self::Id;
+    fn any(&mut self, kids: Vec<Self::Id>) -> Self::Id;
+    fn all(&mut self, kids: Vec<Self::Id>) -> Self::Id;
+    fn not(&mut self, kid: Self::Id) -> Self::Id;
+}
+
+impl<T: Hash + PartialEq> Target<T> for Expression<T> {
+    type Id = NodeId;
+    fn leaf(&mut self, value: T) -> NodeId {
+        self.set(value)
+    }
+    fn any(&mut self, kids: Vec<NodeId>) -> NodeId {
+        self.union(kids)
+    }
+    fn all(&mut self, kids: Vec<NodeId>) -> NodeId {
+        self.intersection(kids)
+    }
+    fn not(&mut self, kid: NodeId) -> NodeId {
+        self.complement(kid)
+    }
+}
+
+impl<T> Target<T> for ExpressionBuilder<T> {
+    type Id = NodeHandle;
+    fn leaf(&mut self, value: T) -> NodeHandle {
+        self.set(value)
+    }
+    // single operands are passed through, so the builder doesn't fill with 1-child groups
+    fn any(&mut self, mut kids: Vec<NodeHandle>) -> NodeHandle {
+        match kids.len() {
+            0 => self.empty(),
+            1 => kids.remove(0),
+            _ => self.union(kids),
+        }
+    }
+    fn all(&mut self, mut kids: Vec<NodeHandle>) -> NodeHandle {
+        if kids.len() == 1 {
+            return kids.remove(0);
+        }
+        self.intersection(kids)
+    }
+    fn not(&mut self, kid: NodeHandle) -> NodeHandle {
+        ExpressionBuilder::not(self, kid)
+    }
+}
+
+struct Parser<'a, 'e, P> {
+    tokens: Vec<(usize, Token<'a>)>,
+    pos: usize,
+    end: usize,
+    target: &'e mut P,
+}
+
+impl<'a, 'e, P> Parser<'a, 'e, P> {
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.pos).map(|&(_, tok)| tok)
+    }
+
+    fn offset(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.end, |&(off, _)| off)
+    }
+
+    fn error(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            offset: self.offset(),
+            kind,
+        }
+    }
+
+    // or   := and ('|' and)*
+    // and  := not ('&' not)*
+    // not  := '!' not | atom
+    // atom := '(' ')' | '(' or ')' | leaf
+    //
+    // open parentheses are kept on an explicit stack rather than recursing, so deeply nested
+    // input can't overflow the call stack
+    fn parse<T: FromStr>(&mut self) -> Result<P::Id, ParseError>
+    where
+        P: Target<T>,
+    {
+        let mut groups = vec![Group::new(0)];
+        loop {
+            // an operand, after any number of prefix negations
+            let group = groups.last_mut().unwrap();
+            while self.peek() == Some(Token::Not) {
+                self.pos += 1;
+                group.nots += 1;
+            }
+            let mut operand = match self.peek() {
+                Some(Token::Open) => {
+                    let open = self.offset();
+                    self.pos += 1;
+                    if self.peek() != Some(Token::Close) {
+                        groups.push(Group::new(open));
+                        continue;
+                    }
+                    // `()` is the empty set
+                    self.pos += 1;
+                    self.target.any(Vec::new())
+                }
+                Some(Token::Leaf(text)) => {
+                    let value = text
+                        .parse()
+                        .map_err(|_| self.error(ParseErrorKind::InvalidLeaf))?;
+                    self.pos += 1;
+                    self.target.leaf(value)
+                }
+                Some(Token::Quoted(text)) => {
+                    let value = unescape(text)
+                        .parse()
+                        .map_err(|_| self.error(ParseErrorKind::InvalidLeaf))?;
+                    self.pos += 1;
+                    self.target.leaf(value)
+                }
+                Some(_) => return Err(self.error(ParseErrorKind::UnexpectedToken)),
+                None => return Err(self.error(ParseErrorKind::UnexpectedEnd)),
+            };
+
+            // the operator after it, closing every group the operand completes
+            loop {
+                let nested = groups.len() > 1;
+                let group = groups.last_mut().unwrap();
+                for _ in 0..mem::take(&mut group.nots) {
+                    operand = self.target.not(operand);
+                }
+                group.ands.push(operand);
+                match self.peek() {
+                    Some(Token::And) => {
+                        self.pos += 1;
+                        break;
+                    }
+                    Some(Token::Or) => {
+                        self.pos += 1;
+                        let ands = mem::take(&mut group.ands);
+                        group.ors.push(self.target.all(ands));
+                        break;
+                    }
+                    Some(Token::Close) if nested => {
+                        self.pos += 1;
+                        let group = groups.pop().unwrap();
+                        operand = self.close(group);
+                    }
+                    None if nested => {
+                        return Err(ParseError {
+                            offset: group.open,
+                            kind: ParseErrorKind::UnclosedParen,
+                        });
+                    }
+                    None => {
+                        let group = groups.pop().unwrap();
+                        return Ok(self.close(group));
+                    }
+                    Some(_) => return Err(self.error(ParseErrorKind::UnexpectedToken)),
+                }
+            }
+        }
+    }
+
+    fn close<T>(&mut self, group: Group<P::Id>) -> P::Id
+    where
+        P: Target<T>,
+    {
+        let Group { mut ors, ands, .. } = group;
+        ors.push(self.target.all(ands));
+        self.target.any(ors)
+    }
+}
+
+/// A parenthesized group still being parsed, or the whole input.
+struct Group<Id> {
+    // offset of the opening parenthesis, unused for the whole input
+    open: usize,
+    ors: Vec<Id>,
+    ands: Vec<Id>,
+    // negations waiting for the next operand
+    nots: usize,
+}
+
+impl<Id> Group<Id> {
+    fn new(open: usize) -> Self {
+        Self {
+            open,
+            ors: Vec::new(),
+            ands: Vec::new(),
+            nots: 0,
+        }
+    }
+}
+
+impl<T: FromStr + Hash + PartialEq> Expression<T> {
+    /// Parses an infix string into a new single-root Expression.
+    ///
+    /// Uses the default [`ParserSyntax`]: `&`, `|`, `!`, and parentheses, with the standard
+    /// precedence `!` > `&` > `|`. Any other run of characters is a leaf, converted with `FromStr`.
+    /// A leaf may also be quoted, `"New York"`, with `\"` and `\\` escaped inside. `()` is the
+    /// empty set. Nesting is limited only by memory, not the call stack, so untrusted input can't
+    /// overflow it.
+    ///
+    /// # Errors
+    /// Returns a [`ParseError`] holding the byte offset of the offending token.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{Expression, parse::{ParseError, ParseErrorKind}};
+    ///
+    /// let expr: Expression<String> = Expression::parse("(Red | Blue) & !Expensive").unwrap();
+    /// let root = expr.roots().next().unwrap();
+    /// assert_eq!(expr.to_string(root), "(([Red] | [Blue]) & [Expensive]')");
+    ///
+    /// let err = Expression::<String>::parse("Red & | Blue").err();
+    /// assert_eq!(err, Some(ParseError { offset: 6, kind: ParseErrorKind::UnexpectedToken }));
+    ///
+    /// let deep = format!("{}Red{}", "!(".repeat(100_000), ")".repeat(100_000));
+    /// assert!(Expression::<String>::parse(&deep).is_ok());
+    /// ```
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        Self::parse_with(input, &ParserSyntax::default())
+    }
+
+    /// Parses an infix string using custom operator tokens.
+    ///
+    /// See [`ParserSyntax`] for how keyword and symbolic operators are matched.
+    ///
+    /// # Errors
+    /// Returns a [`ParseError`] holding the byte offset of the offending token.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{Expression, parse::ParserSyntax};
+    ///
+    /// let syntax = ParserSyntax {
+    ///     and: vec!["*".into()],
+    ///     or: vec!["+".into()],
+    ///     not: vec!["-".into()],
+    /// };
+    /// let expr: Expression<String> = Expression::parse_with("A*-B + C", &syntax).unwrap();
+    /// let root = expr.roots().next().unwrap();
+    /// assert_eq!(expr.to_string(root), "(([A] & [B]') | [C])");
+    /// ```
+    pub fn parse_with(input: &str, syntax: &ParserSyntax) -> Result<Self, ParseError> {
+        let mut expr = Expression::new();
+        let root = parse_into(input, syntax, &mut expr)?;
+        expr.add_root(root);
+        Ok(expr)
+    }
+}
+
+impl<T: Display, Meta> Expression<T, Meta> {
+    /// Formats the root as an infix string that [`Expression::parse`] reads back.
+    ///
+    /// Unlike [`to_string`](Self::to_string), which is meant for debugging, the output uses the
+    /// default [`ParserSyntax`]. Leaves that would not survive tokenizing (whitespace, operators,
+    /// parentheses, or quotes) are wrapped in `"..."` with `\"` and `\\` escaped. The constants
+    /// are written as `()` and `!()`.
+    ///
+    /// Parsing the output gives an equivalent expression, provided the term's `FromStr` undoes
+    /// its `Display`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{Expression, NodeId};
+    ///
+    /// let mut expr = Expression::new();
+    /// let a = expr.set("New York".to_string());
+    /// let b = expr.set("R&D".to_string());
+    /// let c = expr.set("Sales".to_string());
+    /// let not_b = expr.complement(b);
+    /// let root = expr.intersection([a, not_b]);
+    /// let root = expr.union([root, c]);
+    ///
+    /// let text = expr.to_canonical_string(&root);
+    /// assert_eq!(text, r#"(Sales | ("New York" & !"R&D"))"#);
+    ///
+    /// let parsed: Expression<String> = Expression::parse(&text).unwrap();
+    /// expr.add_root(root);
+    /// assert_eq!(parsed.canonical_hash(), expr.canonical_hash());
+    ///
+    /// assert_eq!(expr.to_canonical_string(&NodeId::UNIVERSAL), "!()");
+    /// ```
+    ///
+    /// The round trip holds for generated expressions too, including quoted and escaped leaves,
+    /// roots that fold to a constant, and negated groups:
+    /// ```rust
+    /// use logify::{Expression, NodeId, eval::BoolEval};
+    ///
+    /// let leaves = ["A", "New York", "R&D", r#"say "hi""#, r"C:\my dir", "a|b", "!x", "(y)"];
+    ///
+    /// // a small xorshift generator, so the cases are reproducible
+    /// let mut seed = 0x2545_F491_4F6C_DD1Du64;
+    /// let mut next = move |n: usize| {
+    ///     seed ^= seed << 13;
+    ///     seed ^= seed >> 7;
+    ///     seed ^= seed << 17;
+    ///     seed as usize % n
+    /// };
+    ///
+    /// for _ in 0..200 {
+    ///     let mut expr = Expression::new();
+    ///     let mut ids = vec![NodeId::EMPTY, NodeId::UNIVERSAL];
+    ///     ids.extend(leaves.map(|leaf| expr.set(leaf.to_string())));
+    ///     for _ in 0..6 {
+    ///         let kids: Vec<_> = (0..2 + next(3)).map(|_| ids[next(ids.len())]).collect();
+    ///         let group = if next(2) == 0 { expr.union(kids) } else { expr.intersection(kids) };
+    ///         ids.push(if next(3) == 0 { group.negated() } else { group });
+    ///     }
+    ///     let root = *ids.last().unwrap();
+    ///     expr.add_root(root);
+    ///
+    ///     let text = expr.to_canonical_string(&root);
+    ///     let parsed: Expression<String> = Expression::parse(&text).unwrap();
+    ///
+    ///     // same result for every assignment of the leaves
+    ///     for bits in 0..1 << leaves.len() {
+    ///         let mut ctx = BoolEval::new();
+    ///         for (i, leaf) in leaves.iter().enumerate() {
+    ///             if bits >> i & 1 == 1 {
+    ///                 ctx.add(leaf.to_string());
+    ///             }
+    ///         }
+    ///         assert_eq!(parsed.evaluate(&mut ctx.clone()), expr.evaluate(&mut ctx), "{text}");
+    ///     }
+    /// }
+    /// ```
+    pub fn to_canonical_string(&self, root: &NodeId) -> String {
+        let mut out = String::new();
+        self.write_canonical(root, &mut out);
+        out
+    }
+
+    fn write_canonical(&self, root: &NodeId, out: &mut String) {
+        // nodes still to write, and separators or closing parentheses to write between them
+        enum Pending<'a> {
+            Node(NodeId),
+            Text(&'a str),
+        }
+
+        let mut stack = vec![Pending::Node(*root)];
+        while let Some(pending) = stack.pop() {
+            let id = match pending {
+                Pending::Node(id) => id,
+                Pending::Text(text) => {
+                    out.push_str(text);
+                    continue;
+                }
+            };
+            if id.is_neg() {
+                out.push('!');
+            }
+            let (children, op) = match &self.nodes[id.idx()] {
+                Node::Set(term) => {
+                    write_canonical_leaf(&term.to_string(), out);
+                    continue;
+                }
+                Node::Union(children) => (children, " | "),
+                Node::Intersection(children) => (children, " & "),
+                Node::Empty => {
+                    out.push_str("()");
+                    continue;
+                }
+            };
+            if children.is_empty() {
+                // an empty intersection is everything, an empty union nothing
+                out.push_str(if op == " & " { "!()" } else { "()" });
+                continue;
+            }
+
+            // pushed in reverse, so they pop in reading order
+            out.push('(');
+            stack.push(Pending::Text(")"));
+            for (i, &child) in children.iter().enumerate().rev() {
+                stack.push(Pending::Node(child));
+                if i > 0 {
+                    stack.push(Pending::Text(op));
+                }
+            }
+        }
+    }
+}
+
+fn write_canonical_leaf(text: &str, out: &mut String) {
+    let bare = !text.is_empty()
+        && !text
+            .chars()
+            .any(|c| c.is_whitespace() || "()\"&|!".contains(c));
+    if bare {
+        out.push_str(text);
+        return;
+    }
+    out.push('"');
+    for c in text.chars() {
+        if c == '"' || c == '\\' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('"');
+}
+
+impl<T: FromStr> ExpressionBuilder<T> {
+    /// Parses an infix string into a new builder with a single root.
+    ///
+    /// Accepts the same syntax as [`Expression::parse`]: `&`, `|`, `!`, and parentheses, with
+    /// the standard precedence `!` > `&` > `|`. Nothing is simplified until
+    /// [`build`](Self::build) is called.
+    ///
+    /// # Errors
+    /// Returns a [`ParseError`] holding the byte offset of the offending token.
+    ///
+    /// # Example
+    /// ```rust
+    /// use logify::{ExpressionBuilder, parse::{ParseError, ParseErrorKind}};
+    ///
+    /// let builder = ExpressionBuilder::<String>::parse("(Red | Blue) & !Expensive").unwrap();
+    /// builder.add_root(builder.leaf("Sale")); // keep building before compiling
+    /// let expr = builder.build();
+    ///
+    /// let roots: Vec<_> = expr.roots().map(|r| expr.to_string(r)).collect();
+    /// assert_eq!(roots, ["(([Red] | [Blue]) & [Expensive]')", "[Sale]"]);
+    ///
+    /// let err = ExpressionBuilder::<String>::parse("(Red | Blue").err();
+    /// assert_eq!(err.map(|e| e.offset), Some(0));
+    /// ```
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        Self::parse_with(input, &ParserSyntax::default())
+    }
+
+    /// Parses an infix string into a new builder using custom operator tokens.
+    ///
+    /// See [`ParserSyntax`] for how keyword and symbolic operators are matched.
+    ///
+    /// # Errors
+    /// Returns a [`ParseError`] holding the byte offset of the offending token.
+    pub fn parse_with(input: &str, syntax: &ParserSyntax) -> Result<Self, ParseError> {
+        let mut builder = ExpressionBuilder::new();
+        let root = parse_into(input, syntax, &mut builder)?;
+        builder.add_root(root);
+        Ok(builder)
+    }
+}
+
+fn parse_into<T: FromStr, P: Target<T>>(
+    input: &str,
+    syntax: &ParserSyntax,
+    target: &mut P,
+) -> Result<P::Id, ParseError> {
+    let mut parser = Parser {
+        tokens: tokenize(input, syntax)?,
+        pos: 0,
+        end: input.len(),
+        target,
+    };
+    parser.parse::<T>()
+}